@@ -0,0 +1,63 @@
+use tauri::AppHandle;
+
+use crate::common::{get_app_setting, Browser};
+
+pub const BACKEND_ENV_VAR: &str = "MOZEIDON_BACKEND";
+const BACKEND_SETTING: &str = "backend";
+
+pub const BROWSERS_ENV_VAR: &str = "MOZEIDON_BROWSERS";
+const BROWSERS_SETTING: &str = "browsers";
+
+/// How `MozeidonClient` reaches the browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendMode {
+    /// One `mozeidon-cli` process per query.
+    Sidecar,
+    /// No browser at all, answers come from the `SimulatedBrowser` fixtures.
+    Simulated,
+}
+
+impl BackendMode {
+    pub fn try_from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "sidecar" => Some(BackendMode::Sidecar),
+            "simulated" => Some(BackendMode::Simulated),
+            _ => None,
+        }
+    }
+
+    /// The `MOZEIDON_BACKEND` environment variable wins over the `backend` app setting.
+    /// Defaults to the sidecar.
+    pub fn current(app: &AppHandle) -> Self {
+        std::env::var(BACKEND_ENV_VAR)
+            .ok()
+            .or_else(|| {
+                get_app_setting(app, BACKEND_SETTING)
+                    .and_then(|value| value.as_str().map(String::from))
+            })
+            .and_then(|s| BackendMode::try_from_str(&s))
+            .unwrap_or(BackendMode::Sidecar)
+    }
+}
+
+/// The browsers read requests fan out to, from the comma separated `MOZEIDON_BROWSERS`
+/// environment variable, or else the `browsers` app setting.
+/// Empty means a single, untargeted backend: whichever browser the native app talks to.
+/// Only the simulated backend can target a browser.
+pub fn configured_browsers(app: &AppHandle) -> Vec<Browser> {
+    let names: Vec<String> = match std::env::var(BROWSERS_ENV_VAR) {
+        Ok(value) => value.split(',').map(String::from).collect(),
+        Err(_) => get_app_setting(app, BROWSERS_SETTING)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default(),
+    };
+
+    let mut browsers: Vec<Browser> = Vec::new();
+    for name in names.iter().map(|name| name.trim()) {
+        let browser = Browser::from_str(name);
+        if !name.is_empty() && !browsers.contains(&browser) {
+            browsers.push(browser);
+        }
+    }
+    browsers
+}
@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::commands::browsers::targets;
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::folders::{bookmarks, folder_path};
+use crate::commands::models::BookmarkItem;
+use crate::commands::mozeidon::{execute_batched, invalidate_written};
+use crate::commands::netscape::{self, BookmarkFile, ImportedBookmark};
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::MozeidonRequest;
+use crate::common::Browser;
+
+/// What importing a bookmark file would do.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    /// Folders of the file that do not exist yet, parents first.
+    /// The CLI creates them along with their first bookmark, so folders left empty are not.
+    pub new_folders: Vec<String>,
+    pub new_bookmarks: Vec<ImportedBookmark>,
+    /// Bookmarks of the file already in their folder, or listed twice by the file.
+    pub duplicates: Vec<ImportedBookmark>,
+}
+
+/// What an import did.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    /// New folders holding at least one of the created bookmarks.
+    pub created_folders: usize,
+    pub created_bookmarks: usize,
+    pub skipped_duplicates: usize,
+    /// One message per bookmark that could not be created.
+    pub errors: Vec<String>,
+}
+
+/// Writes the bookmarks of `browser`, or of every configured browser,
+/// to the Netscape bookmark file `path`. Returns how many were written.
+#[tauri::command]
+pub async fn export_bookmarks(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    path: String,
+    browser: Option<Browser>,
+) -> Result<usize, ClientError> {
+    println!("export_bookmarks {} ({:?})", path, browser);
+    let mut seen = HashSet::new();
+    let bookmarks: Vec<BookmarkItem> = bookmarks(&app, &registry, &cache, targets(&app, browser))
+        .await?
+        .into_iter()
+        .filter(|bookmark| seen.insert((bookmark.url.clone(), bookmark.parent.clone())))
+        .collect();
+
+    std::fs::write(&path, netscape::export(&bookmarks))
+        .map_err(|e| ClientError::BookmarkFileAccess(format!("{}: {}", path, e)))?;
+    Ok(bookmarks.len())
+}
+
+/// Reads the Netscape bookmark file `path`, and tells which of its folders and bookmarks
+/// are missing from `browser`. They are put under `into_folder` if given.
+#[tauri::command]
+pub async fn preview_bookmark_import(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    path: String,
+    into_folder: Option<String>,
+    browser: Option<Browser>,
+) -> Result<ImportPreview, ClientError> {
+    println!("preview_bookmark_import {} ({:?})", path, browser);
+    preview(&app, &registry, &cache, &path, into_folder, browser).await
+}
+
+/// Creates the bookmarks of the Netscape bookmark file `path` in `browser`, in their folders,
+/// except for the duplicates found by `preview_bookmark_import`.
+#[tauri::command]
+pub async fn import_bookmarks(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    path: String,
+    into_folder: Option<String>,
+    browser: Option<Browser>,
+) -> Result<ImportSummary, ClientError> {
+    println!("import_bookmarks {} ({:?})", path, browser);
+    let preview = preview(&app, &registry, &cache, &path, into_folder, browser.clone()).await?;
+
+    let mut summary = ImportSummary {
+        created_folders: 0,
+        created_bookmarks: 0,
+        skipped_duplicates: preview.duplicates.len(),
+        errors: Vec::new(),
+    };
+    let mut filled_folders = HashSet::new();
+    let mut last_request = None;
+    for bookmark in preview.new_bookmarks {
+        let request = MozeidonRequest::CreateBookmark {
+            title: bookmark.title,
+            url: bookmark.url,
+            folder: bookmark.folder.clone(),
+        };
+        match execute_batched(&app, &registry, &request, None, browser.clone()).await {
+            Ok(()) => {
+                summary.created_bookmarks += 1;
+                filled_folders.extend(ancestors(&bookmark.folder));
+            }
+            Err(e) => summary.errors.push(format!("{:?}: {}", request, e)),
+        }
+        last_request = Some(request);
+    }
+    if let Some(request) = last_request {
+        invalidate_written(&app, &cache, &request);
+    }
+    summary.created_folders = preview
+        .new_folders
+        .iter()
+        .filter(|folder| filled_folders.contains(*folder))
+        .count();
+
+    println!(
+        "imported {} folders and {} bookmarks, {} errors",
+        summary.created_folders,
+        summary.created_bookmarks,
+        summary.errors.len()
+    );
+    Ok(summary)
+}
+
+async fn preview(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    path: &str,
+    into_folder: Option<String>,
+    browser: Option<Browser>,
+) -> Result<ImportPreview, ClientError> {
+    let html = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::InvalidData => {
+            ClientError::InvalidBookmarkFile(format!("{} is not UTF-8 text", path))
+        }
+        _ => ClientError::BookmarkFileAccess(format!("{}: {}", path, e)),
+    })?;
+    let file = netscape::parse(&html)
+        .ok_or_else(|| ClientError::InvalidBookmarkFile(format!("{} lists no bookmarks", path)))?;
+    let file = relocate(file, into_folder.as_deref())?;
+    // Writes go to `browser` alone, so it is the only one to look for duplicates in.
+    let existing = bookmarks(app, registry, cache, vec![browser]).await?;
+
+    let mut folders: HashSet<String> = HashSet::new();
+    for bookmark in &existing {
+        folders.extend(ancestors(&bookmark.parent));
+    }
+
+    let mut bookmarked: HashSet<(String, String)> = existing
+        .into_iter()
+        .map(|bookmark| (bookmark.url, bookmark.parent))
+        .collect();
+    let (new_bookmarks, duplicates): (Vec<ImportedBookmark>, _) = file
+        .bookmarks
+        .into_iter()
+        .partition(|bookmark| bookmarked.insert((bookmark.url.clone(), bookmark.folder.clone())));
+
+    let filled: HashSet<String> = new_bookmarks
+        .iter()
+        .flat_map(|bookmark| ancestors(&bookmark.folder))
+        .collect();
+    let mut new_folders = Vec::new();
+    for folder in file.folders {
+        if filled.contains(&folder) && folders.insert(folder.clone()) {
+            new_folders.push(folder);
+        }
+    }
+
+    Ok(ImportPreview {
+        new_folders,
+        new_bookmarks,
+        duplicates,
+    })
+}
+
+/// Moves every folder of `file` under `into_folder`.
+fn relocate(
+    mut file: BookmarkFile,
+    into_folder: Option<&str>,
+) -> Result<BookmarkFile, ClientError> {
+    let Some(into_folder) = into_folder else {
+        return Ok(file);
+    };
+    let into_folder = folder_path(into_folder)?;
+    let under = |path: &str| format!("{}{}", into_folder, path.trim_start_matches('/'));
+
+    let mut folders = ancestors(&into_folder);
+    folders.extend(file.folders.iter().map(|folder| under(folder)));
+    file.folders = folders;
+    for bookmark in &mut file.bookmarks {
+        bookmark.folder = under(&bookmark.folder);
+    }
+    Ok(file)
+}
+
+/// `/a/` and `/a/b/` for `/a/b/`.
+fn ancestors(path: &str) -> Vec<String> {
+    let mut ancestors = Vec::new();
+    let mut ancestor = String::from("/");
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        ancestor = format!("{}{}/", ancestor, name);
+        ancestors.push(ancestor.clone());
+    }
+    ancestors
+}
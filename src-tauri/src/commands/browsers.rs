@@ -0,0 +1,110 @@
+use serde::de::DeserializeOwned;
+use tauri::async_runtime::JoinHandle;
+use tauri::AppHandle;
+
+use crate::commands::backend::{configured_browsers, BackendMode};
+use crate::commands::client::{ClientError, StreamSummary};
+use crate::commands::models::BrowserItem;
+use crate::commands::queries::QueryGuard;
+use crate::commands::request::MozeidonRequest;
+use crate::common::Browser;
+
+type BrowserTask<R> = (Option<Browser>, JoinHandle<Result<R, ClientError>>);
+
+/// The browsers a read request goes to: `browser` if the caller picked one,
+/// otherwise every configured browser.
+/// `None` stands for the untargeted backend, when no browser is configured,
+/// and always for the sidecar: `mozeidon-cli` cannot pick a browser, so querying it
+/// once per browser would only read the same items again.
+pub fn targets(app: &AppHandle, browser: Option<Browser>) -> Vec<Option<Browser>> {
+    if BackendMode::current(app) == BackendMode::Sidecar {
+        return vec![None];
+    }
+    if browser.is_some() {
+        return vec![browser];
+    }
+    let browsers = configured_browsers(app);
+    if browsers.is_empty() {
+        vec![None]
+    } else {
+        browsers.into_iter().map(Some).collect()
+    }
+}
+
+/// Runs a read query against every target at the same time,
+/// and merges their items in the order of `targets`.
+pub async fn query_all<T>(
+    app: &AppHandle,
+    guard: &QueryGuard,
+    request: &MozeidonRequest,
+    targets: Vec<Option<Browser>>,
+) -> Result<Vec<T>, ClientError>
+where
+    T: DeserializeOwned + BrowserItem + Send + 'static,
+{
+    let tasks = targets
+        .into_iter()
+        .map(|browser| {
+            let client = guard.client(app, browser.clone());
+            let request = request.clone();
+            let task = tauri::async_runtime::spawn(async move { client.query(&request).await });
+            (browser, task)
+        })
+        .collect();
+
+    Ok(join_all(tasks).await?.into_iter().flatten().collect())
+}
+
+/// Runs a read query against every target at the same time,
+/// handing over each chunk as soon as any of them prints it.
+/// Returns what every browser streamed, added up.
+pub async fn stream_all<T, F>(
+    app: &AppHandle,
+    guard: &QueryGuard,
+    request: &MozeidonRequest,
+    targets: Vec<Option<Browser>>,
+    on_chunk: F,
+) -> Result<StreamSummary, ClientError>
+where
+    T: DeserializeOwned + BrowserItem + Send + 'static,
+    F: FnMut(Vec<T>) + Clone + Send + 'static,
+{
+    let tasks = targets
+        .into_iter()
+        .map(|browser| {
+            let client = guard.client(app, browser.clone());
+            let request = request.clone();
+            let on_chunk = on_chunk.clone();
+            let task =
+                tauri::async_runtime::spawn(async move { client.stream(&request, on_chunk).await });
+            (browser, task)
+        })
+        .collect();
+
+    Ok(join_all(tasks).await?.into_iter().sum())
+}
+
+/// Waits for every browser. One that fails is only logged, as long as another one answered;
+/// otherwise the first error is returned.
+async fn join_all<R>(tasks: Vec<BrowserTask<R>>) -> Result<Vec<R>, ClientError> {
+    let mut results = Vec::new();
+    let mut first_error = None;
+    for (browser, task) in tasks {
+        let result = task
+            .await
+            .map_err(|e| ClientError::Process(e.to_string()))
+            .and_then(|result| result);
+        match result {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                eprintln!("Query failed for browser {:?}: {}", browser, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match first_error {
+        Some(e) if results.is_empty() => Err(e),
+        _ => Ok(results),
+    }
+}
@@ -0,0 +1,340 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::commands::browsers::{query_all, targets};
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::models::TabItem;
+use crate::commands::mozeidon::execute_write;
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::{MozeidonRequest, TabRef};
+use crate::common::{now_ms, Browser};
+
+/// How long a bulk close can be undone.
+const UNDO_TTL: Duration = Duration::from_secs(10 * 60);
+const MAX_UNDO_ENTRIES: usize = 20;
+
+/// Which tabs a bulk operation acts on.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TabFilter {
+    /// Tabs of `domain` or of any of its subdomains.
+    Domain { domain: String },
+    /// Tabs of a window, except its selected one if `except_active`.
+    Window { window_id: u64, except_active: bool },
+    /// Tabs not accessed for `age_ms`.
+    OlderThan { age_ms: u64 },
+    /// The tabs the panel shows, e.g. those matching its search, whatever the search type.
+    Shown { tabs: Vec<ShownTab> },
+}
+
+/// A tab of the panel, as addressed in the browser it was read from.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShownTab {
+    pub id: u64,
+    pub window_id: u64,
+    #[serde(default)]
+    pub browser: Option<Browser>,
+}
+
+impl TabFilter {
+    /// Fails rather than guess when the filter depends on a tab state the backend did not report.
+    fn matches(&self, tab: &TabItem, now_ms: u64) -> Result<bool, ClientError> {
+        Ok(match self {
+            TabFilter::Domain { domain } => {
+                let domain = domain.trim().trim_start_matches("www.").to_lowercase();
+                let tab_domain = tab.domain.to_lowercase();
+                !domain.is_empty()
+                    && (tab_domain == domain || tab_domain.ends_with(&format!(".{}", domain)))
+            }
+            TabFilter::Window {
+                window_id,
+                except_active,
+            } => {
+                tab.window_id == *window_id
+                    && !(*except_active && reported(tab.active, "active state")?)
+            }
+            TabFilter::OlderThan { age_ms } => {
+                now_ms.saturating_sub(reported(tab.last_accessed, "last access time")?) > *age_ms
+            }
+            TabFilter::Shown { tabs } => tabs.iter().any(|shown| {
+                shown.id == tab.id
+                    && shown.window_id == tab.window_id
+                    && shown.browser == tab.browser
+            }),
+        })
+    }
+}
+
+/// A tab state a filter needs, which `mozeidon-cli` does not print.
+fn reported<T>(state: Option<T>, name: &str) -> Result<T, ClientError> {
+    state.ok_or_else(|| {
+        ClientError::Unsupported(format!(
+            "filtering tabs on their {}, which the browser does not report",
+            name
+        ))
+    })
+}
+
+/// What a bulk close did.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkSummary {
+    pub closed: Vec<TabItem>,
+    /// One message per browser that failed to close its tabs.
+    pub errors: Vec<String>,
+    /// Hand it to `undo_close_tabs` to reopen the closed tabs.
+    pub undo_token: Option<String>,
+}
+
+struct UndoEntry {
+    token: String,
+    tabs: Vec<TabItem>,
+    created_at: Instant,
+}
+
+#[derive(Default)]
+struct UndoState {
+    next_token: u64,
+    entries: Vec<UndoEntry>,
+}
+
+/// The tabs closed by the last bulk operations, kept for a while so they can be reopened.
+#[derive(Default, Clone)]
+pub struct BulkUndo {
+    state: Arc<Mutex<UndoState>>,
+}
+
+impl BulkUndo {
+    fn remember(&self, tabs: Vec<TabItem>) -> String {
+        let mut state = self.state.lock().unwrap();
+        state
+            .entries
+            .retain(|entry| entry.created_at.elapsed() < UNDO_TTL);
+        if state.entries.len() >= MAX_UNDO_ENTRIES {
+            state.entries.remove(0);
+        }
+        state.next_token += 1;
+        let token = format!("bulk-{}", state.next_token);
+        state.entries.push(UndoEntry {
+            token: token.clone(),
+            tabs,
+            created_at: Instant::now(),
+        });
+        token
+    }
+
+    /// Removes the entry of `token`, so that it is not undone twice at the same time.
+    fn take(&self, token: &str) -> Option<UndoEntry> {
+        let mut state = self.state.lock().unwrap();
+        let position = state
+            .entries
+            .iter()
+            .position(|entry| entry.token == token)?;
+        let entry = state.entries.remove(position);
+        (entry.created_at.elapsed() < UNDO_TTL).then_some(entry)
+    }
+
+    /// Keeps the tabs of a taken entry that could not be reopened, under the same token.
+    fn put_back(&self, mut entry: UndoEntry, tabs: Vec<TabItem>) {
+        if tabs.is_empty() {
+            return;
+        }
+        entry.tabs = tabs;
+        self.state.lock().unwrap().entries.push(entry);
+    }
+}
+
+/// Closes every tab matching `filter`, in `browser` or in every configured browser.
+/// Pinned tabs are left open unless `include_pinned`.
+/// Nothing is closed when the filter, or leaving pinned tabs open, needs a tab state
+/// the browser does not report.
+#[tauri::command]
+pub async fn close_tabs(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    undo: State<'_, BulkUndo>,
+    filter: TabFilter,
+    browser: Option<Browser>,
+    include_pinned: Option<bool>,
+) -> Result<BulkSummary, ClientError> {
+    let request = MozeidonRequest::GetTabs;
+    let guard = registry.register_background(&request);
+    let tabs = query_all::<TabItem>(&app, &guard, &request, targets(&app, browser)).await?;
+
+    let now = now_ms();
+    let include_pinned = include_pinned.unwrap_or(false);
+    let mut by_browser: Vec<(Option<Browser>, Vec<TabItem>)> = Vec::new();
+    for tab in tabs {
+        if !filter.matches(&tab, now)? {
+            continue;
+        }
+        if !include_pinned && reported(tab.pinned, "pinned state")? {
+            continue;
+        }
+        match by_browser.iter_mut().find(|(b, _)| *b == tab.browser) {
+            Some((_, group)) => group.push(tab),
+            None => by_browser.push((tab.browser.clone(), vec![tab])),
+        }
+    }
+
+    let mut closed = Vec::new();
+    let mut errors = Vec::new();
+    for (browser, group) in by_browser {
+        let request = MozeidonRequest::CloseTabs {
+            tabs: group
+                .iter()
+                .map(|tab| TabRef {
+                    id: tab.id,
+                    window_id: tab.window_id,
+                })
+                .collect(),
+        };
+        match execute_write(&app, &registry, &cache, &request, None, browser.clone()).await {
+            Ok(()) => closed.extend(group),
+            Err(e) => {
+                eprintln!("Failed to close tabs of browser {:?}: {}", browser, e);
+                errors.push(e.to_string());
+            }
+        }
+    }
+
+    let undo_token = (!closed.is_empty()).then(|| undo.remember(closed.clone()));
+    Ok(BulkSummary {
+        closed,
+        errors,
+        undo_token,
+    })
+}
+
+/// Reopens the tabs closed by the bulk operation of `undo_token`, one by one,
+/// each in a new tab of the current window of its browser: the CLI cannot open a tab
+/// in another window, so the former windows are not restored.
+/// Returns how many were reopened. On failure, the tabs not reopened yet can be undone again.
+#[tauri::command]
+pub async fn undo_close_tabs(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    undo: State<'_, BulkUndo>,
+    undo_token: String,
+) -> Result<usize, ClientError> {
+    let mut entry = undo
+        .take(&undo_token)
+        .ok_or_else(|| ClientError::NothingToUndo(undo_token.clone()))?;
+    let mut tabs = std::mem::take(&mut entry.tabs);
+
+    let mut reopened = 0;
+    while let Some(tab) = tabs.first() {
+        let request = MozeidonRequest::OpenTab {
+            url: tab.url.clone(),
+        };
+        if let Err(e) =
+            execute_write(&app, &registry, &cache, &request, None, tab.browser.clone()).await
+        {
+            eprintln!("Failed to reopen {}: {}", tab.url, e);
+            undo.put_back(entry, tabs);
+            return Err(e);
+        }
+        tabs.remove(0);
+        reopened += 1;
+    }
+    Ok(reopened)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tab as printed by `mozeidon-cli`, without any state.
+    fn cli_tab(domain: &str, window_id: u64) -> TabItem {
+        TabItem {
+            id: 1,
+            domain: domain.to_string(),
+            title: String::new(),
+            url: format!("https://{}/", domain),
+            window_id,
+            active: None,
+            last_accessed: None,
+            pinned: None,
+            muted: None,
+            discarded: None,
+            browser: None,
+        }
+    }
+
+    #[test]
+    fn filters_on_reported_fields_match_cli_tabs() {
+        let tab = cli_tab("docs.rs", 2);
+        let domain = TabFilter::Domain {
+            domain: "www.Docs.rs".to_string(),
+        };
+        assert!(domain.matches(&tab, 0).unwrap());
+        let domain = TabFilter::Domain {
+            domain: "crates.io".to_string(),
+        };
+        assert!(!domain.matches(&tab, 0).unwrap());
+
+        let window = TabFilter::Window {
+            window_id: 2,
+            except_active: false,
+        };
+        assert!(window.matches(&tab, 0).unwrap());
+
+        let shown = |window_id| TabFilter::Shown {
+            tabs: vec![ShownTab {
+                id: 1,
+                window_id,
+                browser: None,
+            }],
+        };
+        assert!(shown(2).matches(&tab, 0).unwrap());
+        assert!(!shown(3).matches(&tab, 0).unwrap());
+    }
+
+    #[test]
+    fn unreported_states_are_not_taken_as_false() {
+        let tab = cli_tab("docs.rs", 2);
+        let except_active = TabFilter::Window {
+            window_id: 2,
+            except_active: true,
+        };
+        assert!(matches!(
+            except_active.matches(&tab, 0),
+            Err(ClientError::Unsupported(_))
+        ));
+        let older = TabFilter::OlderThan { age_ms: 1000 };
+        assert!(matches!(
+            older.matches(&tab, 1_000_000),
+            Err(ClientError::Unsupported(_))
+        ));
+        assert!(matches!(
+            reported(tab.pinned, "pinned state"),
+            Err(ClientError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn reported_states_filter_tabs() {
+        let mut tab = cli_tab("docs.rs", 2);
+        tab.active = Some(true);
+        tab.last_accessed = Some(1000);
+        let except_active = TabFilter::Window {
+            window_id: 2,
+            except_active: true,
+        };
+        assert!(!except_active.matches(&tab, 0).unwrap());
+        let older = TabFilter::OlderThan { age_ms: 500 };
+        assert!(older.matches(&tab, 2000).unwrap());
+        assert!(!older.matches(&tab, 1200).unwrap());
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::State;
+
+use crate::commands::request::{MozeidonRequest, QueryContext};
+use crate::common::Browser;
+
+/// Default time to live per context. Contexts left out are never cached,
+/// tabs change far too often for it.
+const DEFAULT_TTLS: &[(QueryContext, Duration)] = &[
+    (QueryContext::Bookmarks, Duration::from_secs(10 * 60)),
+    (QueryContext::History, Duration::from_secs(2 * 60)),
+];
+
+/// What a lookup does with an entry older than its time to live.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheMode {
+    /// The entry is dropped and the items fetched again.
+    #[default]
+    Fresh,
+    /// The entry is returned right away, and refreshed in the background.
+    StaleWhileRevalidate,
+}
+
+/// Same request sent to the same browsers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    context: QueryContext,
+    args: Vec<String>,
+    targets: Vec<Option<Browser>>,
+}
+
+impl CacheKey {
+    pub fn targets(&self) -> &[Option<Browser>] {
+        &self.targets
+    }
+}
+
+pub enum Lookup {
+    Hit(Arc<Vec<Value>>),
+    /// Expired, but returned anyway in `StaleWhileRevalidate` mode.
+    /// The caller is expected to refresh it.
+    Stale(Arc<Vec<Value>>),
+    Miss,
+}
+
+struct CacheEntry {
+    items: Arc<Vec<Value>>,
+    fetched_at: Instant,
+    refreshing: bool,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    ttls: HashMap<QueryContext, Duration>,
+    mode: CacheMode,
+    /// Bumped by every invalidation, so that a fetch started before it
+    /// cannot store items a write has made outdated.
+    generation: u64,
+}
+
+/// Items of the last read queries, kept in memory for a while
+/// so reopening the bookmarks or history panel does not go through the CLI again.
+#[derive(Default, Clone)]
+pub struct ResultCache {
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl ResultCache {
+    /// The key of a read `request` sent to `targets`,
+    /// or `None` when its context is not cached.
+    pub fn key(&self, request: &MozeidonRequest, targets: &[Option<Browser>]) -> Option<CacheKey> {
+        let context = request.context()?;
+        self.ttl_for(context)?;
+        Some(CacheKey {
+            context,
+            args: request.args().ok()?,
+            targets: targets.to_vec(),
+        })
+    }
+
+    pub fn lookup(&self, key: &CacheKey) -> Lookup {
+        let Some(ttl) = self.ttl_for(key.context) else {
+            return Lookup::Miss;
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let mode = state.mode;
+        let Some(entry) = state.entries.get_mut(key) else {
+            return Lookup::Miss;
+        };
+        if entry.fetched_at.elapsed() < ttl {
+            return Lookup::Hit(entry.items.clone());
+        }
+        match mode {
+            CacheMode::StaleWhileRevalidate if entry.refreshing => Lookup::Hit(entry.items.clone()),
+            CacheMode::StaleWhileRevalidate => {
+                entry.refreshing = true;
+                Lookup::Stale(entry.items.clone())
+            }
+            CacheMode::Fresh => {
+                state.entries.remove(key);
+                Lookup::Miss
+            }
+        }
+    }
+
+    /// To be read before fetching, and handed back to `store`.
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    /// Stores freshly fetched items, unless the cache was invalidated since `generation`.
+    pub fn store(&self, key: CacheKey, items: Arc<Vec<Value>>, generation: u64) {
+        let mut state = self.state.lock().unwrap();
+        if state.generation != generation {
+            state.entries.remove(&key);
+            return;
+        }
+        state.entries.insert(
+            key,
+            CacheEntry {
+                items,
+                fetched_at: Instant::now(),
+                refreshing: false,
+            },
+        );
+    }
+
+    /// Lets a later lookup refresh `key` again, after a background refresh failed.
+    pub fn refresh_failed(&self, key: &CacheKey) {
+        if let Some(entry) = self.state.lock().unwrap().entries.get_mut(key) {
+            entry.refreshing = false;
+        }
+    }
+
+    /// Drops every entry of `contexts`.
+    pub fn invalidate(&self, contexts: &[QueryContext]) {
+        if contexts.is_empty() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state
+            .entries
+            .retain(|key, _| !contexts.contains(&key.context));
+    }
+
+    /// Drops the entries a successful write `request` made outdated.
+    pub fn invalidate_for(&self, request: &MozeidonRequest) {
+        let contexts = request.invalidates();
+        if !contexts.is_empty() {
+            println!("invalidating cached {:?}", contexts);
+        }
+        self.invalidate(contexts);
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.entries.clear();
+    }
+
+    fn ttl_for(&self, context: QueryContext) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        state
+            .ttls
+            .get(&context)
+            .copied()
+            .or_else(|| {
+                DEFAULT_TTLS
+                    .iter()
+                    .find(|(c, _)| *c == context)
+                    .map(|(_, ttl)| *ttl)
+            })
+            .filter(|ttl| !ttl.is_zero())
+    }
+
+    pub fn set_ttls(&self, ttls: HashMap<QueryContext, Duration>) {
+        self.state.lock().unwrap().ttls.extend(ttls);
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        self.state.lock().unwrap().mode
+    }
+
+    pub fn set_mode(&self, mode: CacheMode) {
+        self.state.lock().unwrap().mode = mode;
+    }
+}
+
+/// Overrides the time to live of some contexts, in milliseconds. Zero disables caching.
+#[tauri::command]
+pub fn set_cache_options(
+    cache: State<'_, ResultCache>,
+    ttls_ms: Option<HashMap<QueryContext, u64>>,
+    mode: Option<CacheMode>,
+) {
+    println!("set_cache_options {:?} {:?}", ttls_ms, mode);
+    if let Some(ttls_ms) = ttls_ms {
+        cache.set_ttls(
+            ttls_ms
+                .into_iter()
+                .map(|(context, ms)| (context, Duration::from_millis(ms)))
+                .collect(),
+        );
+    }
+    if let Some(mode) = mode {
+        cache.set_mode(mode);
+    }
+}
+
+/// Drops the cached items of `context`, or of every context.
+#[tauri::command]
+pub fn invalidate_cache(cache: State<'_, ResultCache>, context: Option<QueryContext>) {
+    println!("invalidate_cache {:?}", context);
+    match context {
+        Some(context) => cache.invalidate(&[context]),
+        None => cache.clear(),
+    }
+}
@@ -0,0 +1,521 @@
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use tauri::async_runtime::Receiver;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tauri_plugin_shell::ShellExt;
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::commands::backend::BackendMode;
+use crate::commands::models::{BrowserItem, Chunk};
+use crate::commands::ndjson::{decode, Line, LineDecoder, MalformedLine};
+use crate::commands::request::MozeidonRequest;
+use crate::commands::simulated::SimulatedBrowser;
+use crate::common::Browser;
+
+pub const CLI_SIDECAR_NAME: &str = "mozeidon-cli";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The lines the CLI prints on stderr when it cannot reach the native app,
+/// `*` standing for any text, e.g. the path of the socket or an `Error: ` prefix.
+/// Each of them has a case in `tests/fixtures/synthetic/stderr.ndjson`, written by hand:
+/// record the stderr of the CLI there when it prints another one.
+const NATIVE_APP_UNREACHABLE_ERRORS: &[&str] = &[
+    "*dial unix *: connect: connection refused",
+    "*dial unix *: connect: no such file or directory",
+    r"*open \\.\pipe\*: The system cannot find the file specified.",
+    "*timed out trying to connect",
+];
+
+/// The lines the CLI prints on stderr when the native app is up
+/// but the browser extension never answers.
+const EXTENSION_NOT_RESPONDING_ERRORS: &[&str] = &[
+    "*Timed out waiting for the browser",
+    "*context deadline exceeded",
+];
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("Failed to locate sidecar mozeidon-cli: {0}")]
+    CliNotFound(String),
+
+    #[error("Failed to spawn sidecar mozeidon-cli: {0}")]
+    Spawn(String),
+
+    #[error("mozeidon native app is unreachable: {0}")]
+    NativeAppUnreachable(String),
+
+    #[error("mozeidon browser extension is not responding: {0}")]
+    ExtensionNotResponding(String),
+
+    #[error("Failed to parse mozeidon output: {0}")]
+    MalformedOutput(#[from] serde_json::Error),
+
+    #[error("Unexpected mozeidon output: {0}")]
+    UnexpectedOutput(String),
+
+    #[error("mozeidon-cli exited with {}: {stderr}", exit_description(*.code, *.signal))]
+    NonZeroExit {
+        code: Option<i32>,
+        signal: Option<i32>,
+        stderr: String,
+    },
+
+    #[error("mozeidon-cli process error: {0}")]
+    Process(String),
+
+    #[error("mozeidon query was cancelled")]
+    Cancelled,
+
+    #[error("mozeidon query timed out after {0:?}")]
+    TimedOut(Duration),
+
+    #[error("Not supported by mozeidon-cli: {0}")]
+    Unsupported(String),
+
+    #[error("Nothing to undo for {0}, it may have expired")]
+    NothingToUndo(String),
+
+    #[error("Invalid bookmark change: {0}")]
+    InvalidBookmarkChange(String),
+
+    #[error("Failed to access bookmark file {0}")]
+    BookmarkFileAccess(String),
+
+    #[error("Not a bookmark file: {0}")]
+    InvalidBookmarkFile(String),
+}
+
+impl ClientError {
+    /// Stable identifier the frontend can switch on.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClientError::CliNotFound(_) => "cliNotFound",
+            ClientError::Spawn(_) => "spawn",
+            ClientError::NativeAppUnreachable(_) => "nativeAppUnreachable",
+            ClientError::ExtensionNotResponding(_) => "extensionNotResponding",
+            ClientError::MalformedOutput(_) => "malformedOutput",
+            ClientError::UnexpectedOutput(_) => "unexpectedOutput",
+            ClientError::NonZeroExit { .. } => "nonZeroExit",
+            ClientError::Process(_) => "process",
+            ClientError::Cancelled => "cancelled",
+            ClientError::TimedOut(_) => "timedOut",
+            ClientError::Unsupported(_) => "unsupported",
+            ClientError::NothingToUndo(_) => "nothingToUndo",
+            ClientError::InvalidBookmarkChange(_) => "invalidBookmarkChange",
+            ClientError::BookmarkFileAccess(_) => "bookmarkFileAccess",
+            ClientError::InvalidBookmarkFile(_) => "invalidBookmarkFile",
+        }
+    }
+}
+
+/// Errors cross the IPC boundary as `{ kind, message }`.
+impl Serialize for ClientError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("ClientError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+fn exit_description(code: Option<i32>, signal: Option<i32>) -> String {
+    match (code, signal) {
+        (Some(code), _) => format!("code {}", code),
+        (None, Some(signal)) => format!("signal {}", signal),
+        (None, None) => "an unknown status".to_string(),
+    }
+}
+
+/// Sorts a failed run into an error kind, based on its exit status and stderr.
+/// Returns `None` when the CLI exited with 0, whatever it printed on stderr.
+fn classify_failure(code: Option<i32>, signal: Option<i32>, stderr: &str) -> Option<ClientError> {
+    if code == Some(0) {
+        return None;
+    }
+
+    let prints_any = |errors: &[&str]| {
+        stderr
+            .lines()
+            .any(|line| errors.iter().any(|error| is_line(line.trim(), error)))
+    };
+    if prints_any(NATIVE_APP_UNREACHABLE_ERRORS) {
+        return Some(ClientError::NativeAppUnreachable(stderr.trim().to_string()));
+    }
+    if prints_any(EXTENSION_NOT_RESPONDING_ERRORS) {
+        return Some(ClientError::ExtensionNotResponding(
+            stderr.trim().to_string(),
+        ));
+    }
+    Some(ClientError::NonZeroExit {
+        code,
+        signal,
+        stderr: stderr.trim().to_string(),
+    })
+}
+
+/// Whether `line` is the whole of `error`, where `*` stands for any text.
+fn is_line(line: &str, error: &str) -> bool {
+    let mut parts = error.split('*');
+    let Some(mut rest) = line.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        let Some(start) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[start + part.len()..];
+    }
+    rest.ends_with(last)
+}
+
+/// What a streamed query went through.
+#[derive(Debug, Default)]
+pub struct StreamSummary {
+    pub count: usize,
+    /// Lines of output skipped since they could not be decoded.
+    pub malformed: Vec<MalformedLine>,
+}
+
+impl std::iter::Sum for StreamSummary {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |mut total, summary| {
+            total.count += summary.count;
+            total.malformed.extend(summary.malformed);
+            total
+        })
+    }
+}
+
+/// What is producing the events of a run.
+enum Running {
+    Sidecar(CommandChild),
+    Simulated,
+}
+
+/// Single entry point to the `mozeidon-cli` sidecar.
+/// Every command talking to the browser should go through it.
+pub struct MozeidonClient {
+    app: AppHandle,
+    browser: Option<Browser>,
+    timeout: Duration,
+    cancellation: CancellationToken,
+}
+
+impl MozeidonClient {
+    pub fn new(app: &AppHandle) -> Self {
+        Self {
+            app: app.clone(),
+            browser: None,
+            timeout: DEFAULT_TIMEOUT,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Targets `browser` of the simulated backend. Items read through this client get tagged with it.
+    /// `mozeidon-cli` has no option to pick a browser, it always talks to whichever one
+    /// the native app is connected to, see `browsers::targets`.
+    pub fn with_browser(mut self, browser: Option<Browser>) -> Self {
+        self.browser = browser;
+        self
+    }
+
+    /// The sidecar is killed if it has not exited after `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The sidecar is killed as soon as `cancellation` is cancelled.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Spawns the sidecar with the given argv, without waiting for it.
+    pub fn spawn<I, S>(
+        &self,
+        args: I,
+    ) -> Result<(Receiver<CommandEvent>, CommandChild), ClientError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args: Vec<String> = args.into_iter().map(|a| a.as_ref().to_string()).collect();
+        println!("{} {:?} ({:?})", CLI_SIDECAR_NAME, args, self.browser);
+
+        self.app
+            .shell()
+            .sidecar(CLI_SIDECAR_NAME)
+            .map_err(|e| ClientError::CliNotFound(e.to_string()))?
+            .args(args)
+            // Lines are split by `LineDecoder`, whatever the size of the reads.
+            .set_raw_out(true)
+            .spawn()
+            .map_err(|e| match e {
+                tauri_plugin_shell::Error::Io(io) if io.kind() == std::io::ErrorKind::NotFound => {
+                    ClientError::CliNotFound(io.to_string())
+                }
+                e => ClientError::Spawn(e.to_string()),
+            })
+    }
+
+    /// Answers `request` from the simulated browser when that backend is selected,
+    /// otherwise spawns the sidecar.
+    fn start(
+        &self,
+        request: &MozeidonRequest,
+    ) -> Result<(Receiver<CommandEvent>, Running), ClientError> {
+        if BackendMode::current(&self.app) == BackendMode::Simulated {
+            let simulated = self.app.state::<SimulatedBrowser>();
+            let rx = simulated.request(&self.app, self.browser.as_ref(), request);
+            return Ok((rx, Running::Simulated));
+        }
+        let (rx, child) = self.spawn(request.args()?)?;
+        Ok((rx, Running::Sidecar(child)))
+    }
+
+    /// Runs the query to completion, handing over every non-blank stdout line,
+    /// then checks stderr and the exit status.
+    /// The query is stopped on timeout or cancellation.
+    async fn run<F>(&self, request: &MozeidonRequest, mut on_line: F) -> Result<(), ClientError>
+    where
+        F: FnMut(Line) -> Result<(), ClientError>,
+    {
+        let (mut rx, running) = self.start(request)?;
+        let mut running = Some(running);
+        let mut decoder = LineDecoder::default();
+
+        let deadline = tokio::time::sleep(self.timeout);
+        tokio::pin!(deadline);
+
+        let mut stderr = String::new();
+        let mut status = None;
+        loop {
+            let event = tokio::select! {
+                event = rx.recv() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                _ = self.cancellation.cancelled() => {
+                    stop(running.take());
+                    return Err(ClientError::Cancelled);
+                }
+                _ = &mut deadline => {
+                    stop(running.take());
+                    return Err(ClientError::TimedOut(self.timeout));
+                }
+            };
+            match event {
+                CommandEvent::Stdout(bytes) => {
+                    let result = decoder.push(&bytes).into_iter().try_for_each(&mut on_line);
+                    if let Err(e) = result {
+                        stop(running.take());
+                        return Err(e);
+                    }
+                }
+                CommandEvent::Stderr(bytes) => {
+                    let text = String::from_utf8_lossy(&bytes);
+                    eprintln!("{}: {}", CLI_SIDECAR_NAME, text.trim_end());
+                    stderr.push_str(&text);
+                }
+                CommandEvent::Error(message) => {
+                    stop(running.take());
+                    return Err(ClientError::Process(message));
+                }
+                CommandEvent::Terminated(payload) => {
+                    running = None;
+                    status = Some(payload);
+                }
+                _ => {}
+            }
+        }
+        if let Some(line) = decoder.finish() {
+            if let Err(e) = on_line(line) {
+                stop(running.take());
+                return Err(e);
+            }
+        }
+
+        let (code, signal) = status.map_or((None, None), |s| (s.code, s.signal));
+        match classify_failure(code, signal, &stderr) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs the query to completion and returns every stdout line.
+    pub async fn output(&self, request: &MozeidonRequest) -> Result<Vec<String>, ClientError> {
+        let mut lines = Vec::new();
+        self.run(request, |line| {
+            lines.push(line.text);
+            Ok(())
+        })
+        .await?;
+        Ok(lines)
+    }
+
+    /// Runs a read query and decodes every `Chunk<T>` printed on stdout.
+    pub async fn query<T>(&self, request: &MozeidonRequest) -> Result<Vec<T>, ClientError>
+    where
+        T: DeserializeOwned + BrowserItem,
+    {
+        let mut items = Vec::new();
+        self.stream(request, |chunk: Vec<T>| items.extend(chunk))
+            .await?;
+        Ok(items)
+    }
+
+    /// Runs a read query and hands over each decoded `Chunk<T>`
+    /// as soon as it is printed, tagged with the targeted browser.
+    /// Lines that are not chunks, e.g. log messages, are skipped and reported in the summary.
+    /// Fails only when not a single chunk could be decoded.
+    pub async fn stream<T, F>(
+        &self,
+        request: &MozeidonRequest,
+        mut on_chunk: F,
+    ) -> Result<StreamSummary, ClientError>
+    where
+        T: DeserializeOwned + BrowserItem,
+        F: FnMut(Vec<T>),
+    {
+        let mut summary = StreamSummary::default();
+        let mut chunks = 0;
+        self.run(request, |line| {
+            match decode::<Chunk<T>>(&line) {
+                Ok(mut chunk) => {
+                    if let Some(browser) = &self.browser {
+                        for item in &mut chunk.data {
+                            item.set_browser(browser.clone());
+                        }
+                    }
+                    chunks += 1;
+                    summary.count += chunk.data.len();
+                    on_chunk(chunk.data);
+                }
+                Err(mut malformed) => {
+                    malformed.browser = self.browser.clone();
+                    eprintln!("Skipping output of {}, {}", CLI_SIDECAR_NAME, malformed);
+                    summary.malformed.push(malformed);
+                }
+            }
+            Ok(())
+        })
+        .await?;
+
+        match summary.malformed.first() {
+            Some(malformed) if chunks == 0 => {
+                Err(ClientError::UnexpectedOutput(malformed.to_string()))
+            }
+            _ => Ok(summary),
+        }
+    }
+
+    /// Runs a write request, only logging what is printed.
+    pub async fn execute(&self, request: &MozeidonRequest) -> Result<(), ClientError> {
+        self.run(request, |line| {
+            println!("OUTPUT: {}", line.text);
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn stop(running: Option<Running>) {
+    match running {
+        Some(Running::Sidecar(child)) => {
+            println!("killing {} (pid {})", CLI_SIDECAR_NAME, child.pid());
+            if let Err(e) = child.kill() {
+                eprintln!("Failed to kill {}: {}", CLI_SIDECAR_NAME, e);
+            }
+        }
+        Some(Running::Simulated) | None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_zero_is_a_success_whatever_stderr_says() {
+        let stderr = "extension answered after a timeout\n\
+                      open /tmp/mozeidon.log: no such file or directory\n";
+        assert!(classify_failure(Some(0), None, stderr).is_none());
+        assert!(classify_failure(Some(0), None, "").is_none());
+    }
+
+    #[test]
+    fn failures_are_sorted_by_stderr() {
+        let unreachable = classify_failure(
+            Some(1),
+            None,
+            "dial unix /tmp/mozeidon.sock: connect: connection refused",
+        );
+        assert!(matches!(
+            unreachable,
+            Some(ClientError::NativeAppUnreachable(_))
+        ));
+
+        let not_responding = classify_failure(Some(1), None, "Timed out waiting for the browser");
+        assert!(matches!(
+            not_responding,
+            Some(ClientError::ExtensionNotResponding(_))
+        ));
+
+        let other = classify_failure(Some(2), None, "  unknown command \"tabz\"\n");
+        assert!(matches!(
+            other,
+            Some(ClientError::NonZeroExit { code: Some(2), stderr, .. })
+                if stderr == "unknown command \"tabz\""
+        ));
+    }
+
+    #[test]
+    fn cli_errors_are_matched_as_whole_lines() {
+        let cases = include_str!("../../tests/fixtures/synthetic/stderr.ndjson");
+        for case in cases.lines().filter(|line| !line.trim().is_empty()) {
+            let case: serde_json::Value = serde_json::from_str(case).unwrap();
+            let stderr = case["stderr"].as_str().unwrap();
+            let error = classify_failure(Some(1), None, stderr).unwrap();
+            assert_eq!(error.kind(), case["kind"], "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn wildcards_match_any_text() {
+        assert!(is_line(
+            "dial unix /tmp/a.sock: connect: x",
+            "dial unix *: connect: x"
+        ));
+        assert!(is_line("Error: timed out", "*timed out"));
+        assert!(is_line("timed out", "*timed out"));
+        assert!(!is_line("timed out again", "*timed out"));
+        assert!(!is_line("a: b", "a: b: *"));
+    }
+
+    #[test]
+    fn killed_or_vanished_runs_are_failures() {
+        assert!(matches!(
+            classify_failure(None, Some(9), ""),
+            Some(ClientError::NonZeroExit {
+                code: None,
+                signal: Some(9),
+                ..
+            })
+        ));
+        assert!(classify_failure(None, None, "").is_some());
+    }
+}
@@ -0,0 +1,328 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio_util::sync::CancellationToken;
+
+use crate::commands::browsers::targets;
+use crate::commands::client::{ClientError, CLI_SIDECAR_NAME};
+use crate::commands::models::TabItem;
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::MozeidonRequest;
+use crate::common::{
+    get_app_setting, get_sidecar_path, get_user_dir_path, Browser, MANIFEST_FILENAME,
+    NATIVE_HOST_NAME, OS,
+};
+
+pub const CONNECTION_STATUS_EVENT: &str = "connection-status";
+const HEALTH_MONITOR_SETTING: &str = "health_monitor";
+const TRAY_ID: &str = "tray";
+
+const ROUND_TRIP_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The hops between the app and the browser, in the order a query goes through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Hop {
+    Sidecar,
+    Manifest,
+    NativeApp,
+    Extension,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HopStatus {
+    Ok,
+    Failed,
+    /// Not checked, since an earlier hop failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HopCheck {
+    pub hop: Hop,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser: Option<Browser>,
+    pub status: HopStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    pub checks: Vec<HopCheck>,
+    /// The first hop that failed, if any.
+    pub broken_at: Option<Hop>,
+}
+
+/// Emitted as `connection-status` by the health monitor, each time it changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionStatus {
+    pub connected: bool,
+    pub broken_at: Option<Hop>,
+    pub detail: Option<String>,
+}
+
+impl HopCheck {
+    fn new(hop: Hop, browser: Option<Browser>, result: Result<String, String>) -> Self {
+        let (status, detail) = match result {
+            Ok(detail) => (HopStatus::Ok, detail),
+            Err(detail) => (HopStatus::Failed, detail),
+        };
+        Self {
+            hop,
+            browser,
+            status,
+            detail,
+        }
+    }
+
+    fn skipped(hop: Hop, browser: Option<Browser>) -> Self {
+        Self {
+            hop,
+            browser,
+            status: HopStatus::Skipped,
+            detail: String::new(),
+        }
+    }
+}
+
+impl Diagnostics {
+    fn new(checks: Vec<HopCheck>) -> Self {
+        let broken_at = checks
+            .iter()
+            .find(|check| check.status == HopStatus::Failed)
+            .map(|check| check.hop);
+        Self { checks, broken_at }
+    }
+}
+
+/// Checks each hop in order: the sidecars, the native messaging manifests,
+/// then a round-trip through the native app to the extension of every configured browser.
+#[tauri::command]
+pub async fn diagnose_connection(app: AppHandle) -> Diagnostics {
+    let mut checks = vec![
+        HopCheck::new(Hop::Sidecar, None, check_sidecar(&app, CLI_SIDECAR_NAME)),
+        HopCheck::new(Hop::Sidecar, None, check_sidecar(&app, NATIVE_HOST_NAME)),
+    ];
+    let sidecars_ok = checks.iter().all(|check| check.status == HopStatus::Ok);
+
+    for browser in targets(&app, None) {
+        if !sidecars_ok {
+            checks.push(HopCheck::skipped(Hop::Manifest, browser.clone()));
+            checks.extend(skipped_round_trip(browser));
+            continue;
+        }
+        let manifest = HopCheck::new(Hop::Manifest, browser.clone(), check_manifest(&browser));
+        let manifest_ok = manifest.status == HopStatus::Ok;
+        checks.push(manifest);
+        if manifest_ok {
+            checks.extend(round_trip(&app, browser).await);
+        } else {
+            checks.extend(skipped_round_trip(browser));
+        }
+    }
+
+    let diagnostics = Diagnostics::new(checks);
+    println!("diagnose_connection broken at {:?}", diagnostics.broken_at);
+    diagnostics
+}
+
+fn check_sidecar(app: &AppHandle, name: &str) -> Result<String, String> {
+    let path = get_sidecar_path(app, name).map_err(|e| format!("{}: {}", name, e))?;
+    if path.exists() {
+        Ok(path.display().to_string())
+    } else {
+        Err(format!("{} not found at {}", name, path.display()))
+    }
+}
+
+/// The manifest of `browser`, or of any builtin browser when none is targeted.
+fn check_manifest(browser: &Option<Browser>) -> Result<String, String> {
+    let os = OS::current().ok_or("Unsupported platform")?;
+    let browsers = match browser {
+        Some(browser) => std::slice::from_ref(browser),
+        None => Browser::all(),
+    };
+
+    let mut missing = Vec::new();
+    for browser in browsers {
+        match get_user_dir_path(os, browser) {
+            Ok(dir) if dir.join(MANIFEST_FILENAME).exists() => {
+                return Ok(dir.join(MANIFEST_FILENAME).display().to_string())
+            }
+            Ok(dir) => missing.push(dir.join(MANIFEST_FILENAME).display().to_string()),
+            Err(e) => missing.push(format!("{}: {}", browser.name(), e)),
+        }
+    }
+    Err(format!(
+        "No native messaging manifest: {}",
+        missing.join(", ")
+    ))
+}
+
+/// Lists the tabs of `browser`, a query every version of the extension answers,
+/// and sorts a failure into the hop it comes from.
+async fn round_trip(app: &AppHandle, browser: Option<Browser>) -> [HopCheck; 2] {
+    let request = MozeidonRequest::GetTabs;
+    let guard = app.state::<QueryRegistry>().register_background(&request);
+    let client = guard
+        .client(app, browser.clone())
+        .with_timeout(ROUND_TRIP_TIMEOUT);
+
+    match client.query::<TabItem>(&request).await {
+        Ok(tabs) => [
+            HopCheck::new(Hop::NativeApp, browser.clone(), Ok("reachable".to_string())),
+            HopCheck::new(
+                Hop::Extension,
+                browser,
+                Ok(format!("answered with {} tabs", tabs.len())),
+            ),
+        ],
+        Err(e @ (ClientError::ExtensionNotResponding(_) | ClientError::TimedOut(_))) => [
+            HopCheck::new(Hop::NativeApp, browser.clone(), Ok("reachable".to_string())),
+            HopCheck::new(Hop::Extension, browser, Err(e.to_string())),
+        ],
+        Err(e) => [
+            HopCheck::new(Hop::NativeApp, browser.clone(), Err(e.to_string())),
+            HopCheck::skipped(Hop::Extension, browser),
+        ],
+    }
+}
+
+fn skipped_round_trip(browser: Option<Browser>) -> [HopCheck; 2] {
+    [
+        HopCheck::skipped(Hop::NativeApp, browser.clone()),
+        HopCheck::skipped(Hop::Extension, browser),
+    ]
+}
+
+#[derive(Default)]
+struct MonitorState {
+    running: Option<CancellationToken>,
+    last_status: Option<ConnectionStatus>,
+}
+
+/// Periodically checks that every configured browser answers,
+/// and tells the webview and the tray icon when that changes.
+#[derive(Default, Clone)]
+pub struct HealthMonitor {
+    state: Arc<Mutex<MonitorState>>,
+}
+
+impl HealthMonitor {
+    /// Starts the monitor if the `health_monitor` app setting is on.
+    pub fn start_if_enabled(&self, app: &AppHandle) {
+        let enabled = get_app_setting(app, HEALTH_MONITOR_SETTING)
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        if enabled {
+            self.start(app, DEFAULT_MONITOR_INTERVAL);
+        }
+    }
+
+    /// Starts checking every `interval`, restarting the monitor if it already runs.
+    pub fn start(&self, app: &AppHandle, interval: Duration) {
+        let token = CancellationToken::new();
+        if let Some(previous) = self.state.lock().unwrap().running.replace(token.clone()) {
+            previous.cancel();
+        }
+        println!("health monitor started, every {:?}", interval);
+
+        let monitor = self.clone();
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let status = connection_status(&app).await;
+                monitor.update(&app, status);
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    _ = tokio::time::sleep(interval) => {}
+                }
+            }
+            println!("health monitor stopped");
+        });
+    }
+
+    pub fn stop(&self) {
+        if let Some(token) = self.state.lock().unwrap().running.take() {
+            token.cancel();
+        }
+    }
+
+    pub fn last_status(&self) -> Option<ConnectionStatus> {
+        self.state.lock().unwrap().last_status.clone()
+    }
+
+    fn update(&self, app: &AppHandle, status: ConnectionStatus) {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.last_status.as_ref() == Some(&status) {
+                return;
+            }
+            state.last_status = Some(status.clone());
+        }
+
+        println!("connection status changed: {:?}", status);
+        if let Some(tray) = app.tray_by_id(TRAY_ID) {
+            let tooltip = match &status.broken_at {
+                None => "mozeidon : connected".to_string(),
+                Some(hop) => format!("mozeidon : disconnected ({:?})", hop),
+            };
+            if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+                eprintln!("Failed to update tray tooltip: {}", e);
+            }
+        }
+        if let Err(e) = app.emit(CONNECTION_STATUS_EVENT, status) {
+            eprintln!("Failed to emit {}: {}", CONNECTION_STATUS_EVENT, e);
+        }
+    }
+}
+
+/// Only the round-trip hops, which are the ones going down when a browser is closed.
+async fn connection_status(app: &AppHandle) -> ConnectionStatus {
+    let mut checks = Vec::new();
+    for browser in targets(app, None) {
+        checks.extend(round_trip(app, browser).await);
+    }
+    let diagnostics = Diagnostics::new(checks);
+    let detail = diagnostics
+        .checks
+        .iter()
+        .find(|check| check.status == HopStatus::Failed)
+        .map(|check| check.detail.clone());
+    ConnectionStatus {
+        connected: diagnostics.broken_at.is_none(),
+        broken_at: diagnostics.broken_at,
+        detail,
+    }
+}
+
+/// Starts the health monitor, checking every `interval_ms` (30 seconds by default).
+#[tauri::command]
+pub fn start_health_monitor(
+    app: AppHandle,
+    monitor: State<'_, HealthMonitor>,
+    interval_ms: Option<u64>,
+) {
+    let interval = interval_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_MONITOR_INTERVAL);
+    monitor.start(&app, interval);
+}
+
+#[tauri::command]
+pub fn stop_health_monitor(monitor: State<'_, HealthMonitor>) {
+    monitor.stop();
+}
+
+/// The last status seen by the health monitor, `None` until it has run once.
+#[tauri::command]
+pub fn get_connection_status(monitor: State<'_, HealthMonitor>) -> Option<ConnectionStatus> {
+    monitor.last_status()
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, State};
+#[cfg(test)]
+use ts_rs::TS;
+
+use crate::commands::browsers::targets;
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::mozeidon::{index_items, read};
+use crate::commands::queries::QueryRegistry;
+use crate::commands::ranking::{comparable_url, Ranking, RankingWeights};
+use crate::commands::request::MozeidonRequest;
+use crate::common::Browser;
+
+/// Where an item of `get_everything` comes from.
+/// Declared in order of precedence: an open tab hides the bookmark of the same url,
+/// which hides its history row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[cfg_attr(test, derive(TS))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, ts(export, export_to = "../../src/domain/generated/"))]
+pub enum ItemKind {
+    Tab,
+    Bookmark,
+    History,
+}
+
+/// A `TabItem`, `BookmarkItem` or `HistoryItem`, as told by `kind`.
+#[derive(Debug, Serialize)]
+pub struct EverythingItem {
+    pub kind: ItemKind,
+    /// The frecency of the url, see `Ranking`.
+    pub score: f64,
+    #[serde(flatten)]
+    pub item: Value,
+}
+
+/// Reads the tabs, the bookmarks and the history of `browser`, or of every configured browser,
+/// all at the same time, and merges them into one list, one item per url,
+/// ranked by frecency, tabs first on ties.
+/// A source that fails is only logged, as long as another one answered.
+#[tauri::command]
+pub async fn get_everything(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    ranking: State<'_, Ranking>,
+    browser: Option<Browser>,
+    weights: Option<RankingWeights>,
+) -> Result<Vec<EverythingItem>, ClientError> {
+    println!("get_everything ({:?})", browser);
+    let targets = targets(&app, browser);
+    let (tabs, bookmarks, history) = tokio::join!(
+        read_source(
+            &app,
+            &registry,
+            &cache,
+            MozeidonRequest::GetTabs,
+            targets.clone()
+        ),
+        read_source(
+            &app,
+            &registry,
+            &cache,
+            MozeidonRequest::GetBookmarks {
+                max: None,
+                chunk: None,
+            },
+            targets.clone(),
+        ),
+        read_source(
+            &app,
+            &registry,
+            &cache,
+            MozeidonRequest::GetHistory {
+                max: None,
+                chunk: None,
+            },
+            targets,
+        ),
+    );
+
+    let mut sources = Vec::new();
+    let mut first_error = None;
+    for (kind, result) in [
+        (ItemKind::Tab, tabs),
+        (ItemKind::Bookmark, bookmarks),
+        (ItemKind::History, history),
+    ] {
+        match result {
+            Ok(items) => sources.push((kind, items)),
+            Err(e) => {
+                eprintln!("Failed to read {:?} items: {}", kind, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if let Some(e) = first_error.filter(|_| sources.is_empty()) {
+        return Err(e);
+    }
+
+    let items = merge(&sources, ranking.scorer(weights));
+    println!("{} items in everything", items.len());
+    Ok(items)
+}
+
+/// The items of a read `request`, indexed for `search` as if the panel had read them.
+async fn read_source(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    request: MozeidonRequest,
+    targets: Vec<Option<Browser>>,
+) -> Result<Arc<Vec<Value>>, ClientError> {
+    let guard = registry.register_background(&request);
+    let items = read(app, cache, &guard, &request, targets).await?;
+    if let Some(context) = request.context() {
+        index_items(app, context, &items);
+    }
+    Ok(items)
+}
+
+/// Keeps the first item of each url, as compared by `comparable_url`,
+/// `sources` being in order of precedence, and sorts them by score.
+fn merge(
+    sources: &[(ItemKind, Arc<Vec<Value>>)],
+    score: impl Fn(&str, f64) -> f64,
+) -> Vec<EverythingItem> {
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    for (kind, items) in sources {
+        for item in items.iter() {
+            let url = item.get("url").and_then(Value::as_str).unwrap_or_default();
+            if !seen.insert(comparable_url(url)) {
+                continue;
+            }
+            merged.push(EverythingItem {
+                kind: *kind,
+                score: score(url, 0.0),
+                item: item.clone(),
+            });
+        }
+    }
+    merged.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.kind.cmp(&b.kind)));
+    merged
+}
@@ -0,0 +1,387 @@
+use serde::Serialize;
+use tauri::{AppHandle, State};
+#[cfg(test)]
+use ts_rs::TS;
+
+use crate::commands::browsers::targets;
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::models::BookmarkItem;
+use crate::commands::mozeidon::{execute_batched, execute_write, invalidate_written, read};
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::MozeidonRequest;
+use crate::common::Browser;
+
+/// A bookmark folder, and the folders it contains.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(TS))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, ts(export, export_to = "../../src/domain/generated/"))]
+pub struct BookmarkFolder {
+    pub name: String,
+    /// Folder path, e.g. `/Bookmarks Toolbar/dev/`, as in `BookmarkItem::parent`.
+    pub path: String,
+    /// Bookmarks directly in this folder.
+    pub item_count: u32,
+    /// Bookmarks in this folder and its subfolders.
+    pub total_count: u32,
+    pub children: Vec<BookmarkFolder>,
+}
+
+/// The folders of one browser, under a root folder of path `/`.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(test, derive(TS))]
+#[cfg_attr(test, ts(export, export_to = "../../src/domain/generated/"))]
+pub struct BookmarkTree {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional, type = "string"))]
+    pub browser: Option<Browser>,
+    pub root: BookmarkFolder,
+}
+
+impl BookmarkFolder {
+    fn root() -> Self {
+        BookmarkFolder {
+            name: String::new(),
+            path: "/".to_string(),
+            item_count: 0,
+            total_count: 0,
+            children: Vec::new(),
+        }
+    }
+
+    /// The folder of `path`, created with its parents as needed.
+    fn folder(&mut self, path: &str) -> &mut BookmarkFolder {
+        let mut folder = self;
+        for name in segments(path) {
+            let position = match folder.children.iter().position(|child| child.name == name) {
+                Some(position) => position,
+                None => {
+                    folder.children.push(BookmarkFolder {
+                        name: name.to_string(),
+                        path: format!("{}{}/", folder.path, name),
+                        item_count: 0,
+                        total_count: 0,
+                        children: Vec::new(),
+                    });
+                    folder.children.len() - 1
+                }
+            };
+            folder = &mut folder.children[position];
+        }
+        folder
+    }
+
+    /// Counts a bookmark of `path` in every folder down to it, creating them as needed.
+    fn add_bookmark(&mut self, path: &str) {
+        let mut folder = self;
+        folder.total_count += 1;
+        for name in segments(path) {
+            folder = folder.folder(name);
+            folder.total_count += 1;
+        }
+        folder.item_count += 1;
+    }
+}
+
+/// The folder tree of `browser`, or one per configured browser.
+/// It is built from the folder listing of the backend, so that empty folders are part of it.
+/// `mozeidon-cli` cannot list folders: the tree then holds the folders of the bookmarks only.
+#[tauri::command]
+pub async fn get_bookmark_folders(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    browser: Option<Browser>,
+) -> Result<Vec<BookmarkTree>, ClientError> {
+    println!("get_bookmark_folders ({:?})", browser);
+    let targets = targets(&app, browser);
+    let bookmarks = bookmarks(&app, &registry, &cache, targets.clone()).await?;
+
+    let mut trees = Vec::new();
+    for browser in targets {
+        let mut root = BookmarkFolder::root();
+        for path in folders(&app, &registry, browser.clone()).await? {
+            root.folder(&path);
+        }
+        trees.push(BookmarkTree { browser, root });
+    }
+    for bookmark in bookmarks {
+        let tree = match trees.iter().position(|t| t.browser == bookmark.browser) {
+            Some(position) => &mut trees[position],
+            None => {
+                trees.push(BookmarkTree {
+                    browser: bookmark.browser.clone(),
+                    root: BookmarkFolder::root(),
+                });
+                trees.last_mut().unwrap()
+            }
+        };
+        tree.root.add_bookmark(&bookmark.parent);
+    }
+    Ok(trees)
+}
+
+/// Creates the empty folder of `path`.
+/// Not supported by `mozeidon-cli`, which creates folders along with their bookmarks.
+#[tauri::command]
+pub async fn create_bookmark_folder(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    path: String,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    let request = MozeidonRequest::CreateFolder {
+        path: folder_path(&path)?,
+    };
+    println!("{} {:?} ({:?})", request.name(), request, browser);
+    execute_write(&app, &registry, &cache, &request, None, browser).await
+}
+
+/// Renames the last segment of `path` to `name`, by moving each of its bookmarks.
+#[tauri::command]
+pub async fn rename_bookmark_folder(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    path: String,
+    name: String,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    println!(
+        "rename_bookmark_folder {} to {} ({:?})",
+        path, name, browser
+    );
+    let name = name.trim();
+    if name.is_empty() || name.contains('/') {
+        return Err(invalid(format!("{:?} is not a valid folder name", name)));
+    }
+    let path = existing_folder_path(&path)?;
+    let parent = path
+        .trim_end_matches('/')
+        .rsplit_once('/')
+        .map_or("", |(parent, _)| parent);
+    let new_path = format!("{}/{}/", parent, name);
+    move_folder_content(&app, &registry, &cache, &path, &new_path, browser).await
+}
+
+/// Moves the folder of `path`, with its content, into `to_parent`,
+/// by moving each of its bookmarks.
+#[tauri::command]
+pub async fn move_bookmark_folder(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    path: String,
+    to_parent: String,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    println!(
+        "move_bookmark_folder {} to {} ({:?})",
+        path, to_parent, browser
+    );
+    let path = existing_folder_path(&path)?;
+    let to_parent = folder_path(&to_parent)?;
+    if to_parent.starts_with(&path) {
+        return Err(invalid(format!("Cannot move {} into itself", path)));
+    }
+    let name = segments(&path).last().unwrap_or_default();
+    let new_path = format!("{}{}/", to_parent, name);
+    move_folder_content(&app, &registry, &cache, &path, &new_path, browser).await
+}
+
+/// Deletes the folder of `path`, by deleting each of its bookmarks.
+/// Unless `recursive`, it must be empty, which the CLI cannot delete.
+#[tauri::command]
+pub async fn delete_bookmark_folder(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    path: String,
+    recursive: Option<bool>,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    println!("delete_bookmark_folder {} ({:?})", path, browser);
+    let path = existing_folder_path(&path)?;
+    let content = folder_content(&app, &registry, &cache, &path, browser.clone()).await?;
+    if content.is_empty() {
+        return Err(ClientError::Unsupported(format!(
+            "deleting the empty folder {}",
+            path
+        )));
+    }
+    if !recursive.unwrap_or(false) {
+        return Err(invalid(format!("Folder {} is not empty", path)));
+    }
+    let requests = content
+        .into_iter()
+        .map(|bookmark| MozeidonRequest::DeleteBookmark { id: bookmark.id })
+        .collect();
+    execute_all(&app, &registry, &cache, requests, browser).await
+}
+
+/// Moves the bookmarks of `ids` into `folder`, which is created if needed.
+#[tauri::command]
+pub async fn move_bookmarks(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    ids: Vec<String>,
+    folder: String,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    println!("move_bookmarks {:?} to {} ({:?})", ids, folder, browser);
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let folder = folder_path(&folder)?;
+    let bookmarks = bookmarks(&app, &registry, &cache, vec![browser.clone()]).await?;
+    let requests = ids
+        .iter()
+        .map(|id| {
+            let bookmark = bookmarks
+                .iter()
+                .find(|bookmark| &bookmark.id == id)
+                .ok_or_else(|| invalid(format!("No bookmark {}", id)))?;
+            Ok(MozeidonRequest::UpdateBookmark {
+                id: id.clone(),
+                title: bookmark.title.clone(),
+                url: bookmark.url.clone(),
+                folder: folder.clone(),
+            })
+        })
+        .collect::<Result<_, ClientError>>()?;
+    execute_all(&app, &registry, &cache, requests, browser).await
+}
+
+/// Gives every bookmark of the folder `path`, and of its subfolders, the path `new_path`.
+/// Empty subfolders stay where they are, the CLI having no folder operations.
+async fn move_folder_content(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    path: &str,
+    new_path: &str,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    let content = folder_content(app, registry, cache, path, browser.clone()).await?;
+    if content.is_empty() {
+        return Err(ClientError::Unsupported(format!(
+            "moving the empty folder {}",
+            path
+        )));
+    }
+    let requests = content
+        .into_iter()
+        .map(|bookmark| MozeidonRequest::UpdateBookmark {
+            folder: format!("{}{}", new_path, &bookmark.parent[path.len()..]),
+            id: bookmark.id,
+            title: bookmark.title,
+            url: bookmark.url,
+        })
+        .collect();
+    execute_all(app, registry, cache, requests, browser).await
+}
+
+/// Runs the writes of `requests` one after the other, up to the first failure,
+/// then drops the cached bookmarks once.
+async fn execute_all(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    requests: Vec<MozeidonRequest>,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    let mut result = Ok(());
+    for request in &requests {
+        result = execute_batched(app, registry, request, None, browser.clone()).await;
+        if result.is_err() {
+            break;
+        }
+    }
+    if let Some(request) = requests.first() {
+        invalidate_written(app, cache, request);
+    }
+    result
+}
+
+/// The bookmarks of the folder `path` and of its subfolders.
+async fn folder_content(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    path: &str,
+    browser: Option<Browser>,
+) -> Result<Vec<BookmarkItem>, ClientError> {
+    let bookmarks = bookmarks(app, registry, cache, vec![browser]).await?;
+    Ok(bookmarks
+        .into_iter()
+        .filter(|bookmark| bookmark.parent.starts_with(path))
+        .collect())
+}
+
+/// Every bookmark of `targets`, from the `ResultCache` if it holds them.
+pub async fn bookmarks(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    targets: Vec<Option<Browser>>,
+) -> Result<Vec<BookmarkItem>, ClientError> {
+    let request = MozeidonRequest::GetBookmarks {
+        max: None,
+        chunk: None,
+    };
+    let guard = registry.register_background(&request);
+    let items = read(app, cache, &guard, &request, targets).await?;
+    items
+        .iter()
+        .map(|item| serde_json::from_value(item.clone()).map_err(ClientError::from))
+        .collect()
+}
+
+/// The folder paths listed by the backend of `browser`, none if it cannot list them.
+async fn folders(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    browser: Option<Browser>,
+) -> Result<Vec<String>, ClientError> {
+    let request = MozeidonRequest::GetFolders;
+    let (client, _guard) = registry.client(app, &request, None, browser);
+    match client.output(&request).await {
+        Ok(lines) => lines
+            .iter()
+            .map(|line| serde_json::from_str(line).map_err(ClientError::from))
+            .collect(),
+        Err(ClientError::Unsupported(_)) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn invalid(message: String) -> ClientError {
+    ClientError::InvalidBookmarkChange(message)
+}
+
+/// `path` with its segments trimmed, between a leading and a trailing `/`.
+pub fn folder_path(path: &str) -> Result<String, ClientError> {
+    if !path.trim().starts_with('/') {
+        return Err(invalid(format!("Folder path {:?} must start with /", path)));
+    }
+    let normalized: String = segments(path).map(|name| format!("/{}", name)).collect();
+    Ok(format!("{}/", normalized))
+}
+
+/// Same as `folder_path`, but the root folder is refused.
+fn existing_folder_path(path: &str) -> Result<String, ClientError> {
+    let path = folder_path(path)?;
+    if path == "/" {
+        return Err(invalid("The root folder cannot be changed".to_string()));
+    }
+    Ok(path)
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
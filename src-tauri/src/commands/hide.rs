@@ -1,17 +1,22 @@
+use crate::commands::QueryRegistry;
 use crate::common::MAIN_WINDOW_LABEL;
 /* import trait Manager */
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, State};
 #[cfg(target_os = "macos")]
 use tauri_nspanel::ManagerExt;
 
 #[tauri::command]
-pub fn hide(app_handle: AppHandle) {
+pub fn hide(app_handle: AppHandle, registry: State<'_, QueryRegistry>) {
     println!("hide");
+    /* nothing will display the results of pending queries */
+    registry.cancel_reads();
     #[cfg(not(target_os = "macos"))]
     {
         let window = app_handle.get_webview_window(MAIN_WINDOW_LABEL).unwrap();
         if window.is_visible().unwrap() {
-            window.hide();
+            if let Err(e) = window.hide() {
+                eprintln!("Failed to hide {}: {}", MAIN_WINDOW_LABEL, e);
+            }
         }
     }
     #[cfg(target_os = "macos")]
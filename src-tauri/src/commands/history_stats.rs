@@ -0,0 +1,266 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::commands::browsers::targets;
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::models::{BookmarkItem, HistoryItem};
+use crate::commands::mozeidon::read;
+use crate::commands::queries::QueryRegistry;
+use crate::commands::ranking::comparable_url;
+use crate::commands::request::MozeidonRequest;
+use crate::common::{date, domain_of, now_ms, Browser};
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+const HOUR_MS: u64 = 60 * 60 * 1000;
+/// `days` is clamped to a year, as `last_visits_per_day` holds one entry per day.
+const MAX_DAYS: u32 = 366;
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StatsOptions {
+    /// How many days back `last_visits_per_day` and `new_domains` look, from 1 to `MAX_DAYS`.
+    pub days: u32,
+    /// How long the top lists are.
+    pub top: usize,
+    /// How many visits make a page frequently visited.
+    pub min_visits: u64,
+    /// Days and hours are those of the user's timezone, e.g. `120` for UTC+2.
+    pub utc_offset_minutes: i32,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        StatsOptions {
+            days: 30,
+            top: 10,
+            min_visits: 5,
+            utc_offset_minutes: 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainVisits {
+    pub domain: String,
+    pub visits: u64,
+    /// Visits typed in the address bar.
+    pub typed: u64,
+    pub pages: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayCount {
+    /// `YYYY-MM-DD`.
+    pub day: String,
+    pub pages: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewDomain {
+    pub domain: String,
+    /// The last visit of the least recently visited page of the domain,
+    /// in milliseconds since the epoch. The history does not keep first visits,
+    /// so this is the closest to when the domain was first visited.
+    pub oldest_last_visit_time: u64,
+    pub pages: usize,
+}
+
+/// Statistics of the browsing history.
+/// The history only tells when each page was last visited,
+/// so the visits per day and per hour count pages by their last visit.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryStats {
+    pub pages: usize,
+    pub visits: u64,
+    pub top_domains: Vec<DomainVisits>,
+    /// One entry per day of the period, oldest first, days without visits included.
+    pub last_visits_per_day: Vec<DayCount>,
+    /// One entry per hour of the day, from 0 to 23.
+    pub last_visits_per_hour: Vec<usize>,
+    /// Domains whose every page was visited once, and last visited during the period.
+    /// Most recent first.
+    pub new_domains: Vec<NewDomain>,
+    /// Pages visited at least `min_visits` times, but not bookmarked.
+    pub unbookmarked: Vec<HistoryItem>,
+}
+
+#[derive(Default)]
+struct DomainEntry {
+    visits: u64,
+    typed: u64,
+    pages: usize,
+    visited_once: bool,
+    oldest_last_visit_time: u64,
+}
+
+/// Aggregates the history of `browser`, or of every configured browser.
+#[tauri::command]
+pub async fn get_history_stats(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    browser: Option<Browser>,
+    options: Option<StatsOptions>,
+) -> Result<HistoryStats, ClientError> {
+    println!("get_history_stats {:?} ({:?})", options, browser);
+    let options = options.unwrap_or_default();
+    let targets = targets(&app, browser);
+    let history: Vec<HistoryItem> = read_items(
+        &app,
+        &registry,
+        &cache,
+        MozeidonRequest::GetHistory {
+            max: None,
+            chunk: None,
+        },
+        targets.clone(),
+    )
+    .await?;
+    let bookmarks: Vec<BookmarkItem> = read_items(
+        &app,
+        &registry,
+        &cache,
+        MozeidonRequest::GetBookmarks {
+            max: None,
+            chunk: None,
+        },
+        targets,
+    )
+    .await?;
+
+    let stats = aggregate(history, &bookmarks, &options, now_ms());
+    println!(
+        "history stats of {} pages, {} domains",
+        stats.pages,
+        stats.top_domains.len()
+    );
+    Ok(stats)
+}
+
+fn aggregate(
+    history: Vec<HistoryItem>,
+    bookmarks: &[BookmarkItem],
+    options: &StatsOptions,
+    now: u64,
+) -> HistoryStats {
+    let offset_ms = options.utc_offset_minutes as i64 * 60 * 1000;
+    let local_day = |time: u64| (time as i64 + offset_ms).div_euclid(DAY_MS as i64);
+    let today = local_day(now);
+    let days = options.days.clamp(1, MAX_DAYS);
+    let first_day = today - days as i64 + 1;
+
+    let mut domains: HashMap<String, DomainEntry> = HashMap::new();
+    let mut per_day = vec![0; days as usize];
+    let mut per_hour = vec![0; 24];
+    for item in &history {
+        let entry = domains
+            .entry(domain_of(&item.url))
+            .or_insert_with(|| DomainEntry {
+                visited_once: true,
+                oldest_last_visit_time: u64::MAX,
+                ..Default::default()
+            });
+        entry.visits += item.visit_count;
+        entry.typed += item.typed_count;
+        entry.pages += 1;
+        entry.visited_once &= item.visit_count <= 1;
+        entry.oldest_last_visit_time = entry.oldest_last_visit_time.min(item.last_visit_time);
+
+        if item.last_visit_time == 0 {
+            continue;
+        }
+        let day = local_day(item.last_visit_time);
+        if day >= first_day && day <= today {
+            per_day[(day - first_day) as usize] += 1;
+        }
+        let local_time = (item.last_visit_time as i64 + offset_ms).rem_euclid(DAY_MS as i64);
+        per_hour[(local_time as u64 / HOUR_MS) as usize] += 1;
+    }
+
+    let mut new_domains: Vec<NewDomain> = domains
+        .iter()
+        .filter(|(domain, entry)| {
+            !domain.is_empty()
+                && entry.visited_once
+                && entry.oldest_last_visit_time != 0
+                && local_day(entry.oldest_last_visit_time) >= first_day
+        })
+        .map(|(domain, entry)| NewDomain {
+            domain: domain.clone(),
+            oldest_last_visit_time: entry.oldest_last_visit_time,
+            pages: entry.pages,
+        })
+        .collect();
+    new_domains.sort_by_key(|domain| Reverse(domain.oldest_last_visit_time));
+    new_domains.truncate(options.top);
+
+    let mut top_domains: Vec<DomainVisits> = domains
+        .into_iter()
+        .filter(|(domain, _)| !domain.is_empty())
+        .map(|(domain, entry)| DomainVisits {
+            domain,
+            visits: entry.visits,
+            typed: entry.typed,
+            pages: entry.pages,
+        })
+        .collect();
+    top_domains.sort_by(|a, b| b.visits.cmp(&a.visits).then(a.domain.cmp(&b.domain)));
+    top_domains.truncate(options.top);
+
+    let bookmarked: HashSet<String> = bookmarks
+        .iter()
+        .map(|bookmark| comparable_url(&bookmark.url))
+        .collect();
+    let pages = history.len();
+    let visits = history.iter().map(|item| item.visit_count).sum();
+    let mut unbookmarked: Vec<HistoryItem> = history
+        .into_iter()
+        .filter(|item| {
+            item.visit_count >= options.min_visits
+                && !bookmarked.contains(&comparable_url(&item.url))
+        })
+        .collect();
+    unbookmarked.sort_by_key(|item| Reverse(item.visit_count));
+    unbookmarked.truncate(options.top);
+
+    HistoryStats {
+        pages,
+        visits,
+        top_domains,
+        last_visits_per_day: per_day
+            .into_iter()
+            .enumerate()
+            .map(|(index, pages)| DayCount {
+                day: date(first_day + index as i64),
+                pages,
+            })
+            .collect(),
+        last_visits_per_hour: per_hour,
+        new_domains,
+        unbookmarked,
+    }
+}
+
+async fn read_items<T: DeserializeOwned>(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    request: MozeidonRequest,
+    targets: Vec<Option<Browser>>,
+) -> Result<Vec<T>, ClientError> {
+    let guard = registry.register_background(&request);
+    let items = read(app, cache, &guard, &request, targets).await?;
+    Ok(items
+        .iter()
+        .map(|item| serde_json::from_value(item.clone()))
+        .collect::<Result<_, _>>()?)
+}
@@ -1,10 +1,10 @@
-use dirs::home_dir;
 use tauri::AppHandle;
 
+use crate::common;
+
 #[tauri::command]
 pub async fn get_user_home_dir(_app: AppHandle) -> String {
-    let home = home_dir();
-    home.unwrap_or_default().to_string_lossy().into_owned()
+    common::get_user_home_dir()
 }
 
 #[cfg(target_os = "linux")]
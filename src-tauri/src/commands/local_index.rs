@@ -0,0 +1,547 @@
+//! A SQLite copy of the bookmarks and the history of each browser, under the app data dir,
+//! so that the first read after a restart does not wait for the browser to stream them again.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection, Transaction};
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::browsers::targets;
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::models::{BookmarkItem, HistoryItem};
+use crate::commands::mozeidon::fetch;
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::{MozeidonRequest, QueryContext, DEFAULT_MAX_ITEMS};
+use crate::common::Browser;
+
+const INDEX_FILENAME: &str = "index.sqlite";
+
+/// Rows are keyed by browser, `''` standing for the untargeted backend.
+/// `synced` lists the tables holding every item of a browser.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS bookmarks (
+    browser TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    parent TEXT NOT NULL,
+    PRIMARY KEY (browser, id)
+);
+CREATE TABLE IF NOT EXISTS history (
+    browser TEXT NOT NULL,
+    id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    typed_count INTEGER NOT NULL,
+    visit_count INTEGER NOT NULL,
+    last_visit_time INTEGER NOT NULL,
+    PRIMARY KEY (browser, id)
+);
+CREATE INDEX IF NOT EXISTS history_by_visit ON history (browser, last_visit_time);
+CREATE INDEX IF NOT EXISTS history_by_url ON history (browser, url);
+CREATE TABLE IF NOT EXISTS synced (
+    browser TEXT NOT NULL,
+    source TEXT NOT NULL,
+    PRIMARY KEY (browser, source)
+);
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Table {
+    Bookmarks,
+    History,
+}
+
+impl Table {
+    fn name(self) -> &'static str {
+        match self {
+            Table::Bookmarks => "bookmarks",
+            Table::History => "history",
+        }
+    }
+}
+
+/// How the items of a read request are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Write {
+    /// Every item of the browser, replacing the indexed ones.
+    Full,
+    /// The whole history again, as the CLI cannot filter it by visit time,
+    /// diffed against the indexed one: only the pages visited at that time or later are written,
+    /// the older ones being held by the index already. Saves the writes, not the read.
+    Diff(u64),
+    /// Some of the items only, which are not kept.
+    Partial,
+}
+
+#[derive(Default)]
+struct IndexState {
+    /// `None` when the database could not be opened, reads then always go to the browser.
+    connection: Option<Connection>,
+    syncing: bool,
+}
+
+/// The bookmarks and the history last read from each browser, kept on disk.
+/// A read the `ResultCache` misses in `CacheMode::StaleWhileRevalidate` is answered from it,
+/// and the index is synced in the background, see `sync`.
+/// It also answers the reads the CLI fails.
+#[derive(Default, Clone)]
+pub struct LocalIndex {
+    state: Arc<Mutex<IndexState>>,
+}
+
+impl LocalIndex {
+    /// Opens the index under the app data dir, creating it if needed.
+    pub fn open(app: &AppHandle) -> Self {
+        let connection = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|dir| open_database(&dir));
+        let connection = match connection {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                eprintln!("Local index disabled: {}", e);
+                None
+            }
+        };
+        LocalIndex {
+            state: Arc::new(Mutex::new(IndexState {
+                connection,
+                syncing: false,
+            })),
+        }
+    }
+
+    /// The indexed items of a read `request` against `targets`,
+    /// or `None` unless every target was synced.
+    /// Read on a blocking thread, as a history can be 100 000 rows.
+    pub async fn items(
+        &self,
+        request: &MozeidonRequest,
+        targets: &[Option<Browser>],
+    ) -> Option<Arc<Vec<Value>>> {
+        let (table, max) = match request {
+            MozeidonRequest::GetBookmarks { max, .. } => (Table::Bookmarks, *max),
+            MozeidonRequest::GetHistory { max, .. } => (Table::History, *max),
+            _ => return None,
+        };
+        let max = max.unwrap_or(DEFAULT_MAX_ITEMS);
+        let targets = targets.to_vec();
+        self.blocking(move |index| index.select_items(table, &targets, max))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    fn select_items(
+        &self,
+        table: Table,
+        targets: &[Option<Browser>],
+        max: u32,
+    ) -> Option<Arc<Vec<Value>>> {
+        let state = self.state.lock().unwrap();
+        let connection = state.connection.as_ref()?;
+
+        let mut items = Vec::new();
+        for browser in targets {
+            let rows = is_synced(connection, table, browser).and_then(|synced| {
+                synced
+                    .then(|| select(connection, table, browser, max))
+                    .transpose()
+            });
+            match rows {
+                Ok(Some(rows)) => items.extend(rows),
+                Ok(None) => return None,
+                Err(e) => {
+                    eprintln!("Failed to read the local index: {}", e);
+                    return None;
+                }
+            }
+        }
+        Some(Arc::new(items))
+    }
+
+    /// Keeps the items of a read `request` against `targets`, on a blocking thread,
+    /// as a history can be 100 000 rows.
+    /// A browser without any item is left as it is, its query may just have failed.
+    pub fn store(
+        &self,
+        request: &MozeidonRequest,
+        targets: &[Option<Browser>],
+        items: Arc<Vec<Value>>,
+    ) {
+        let Some((table, write)) = kept(request).filter(|(_, write)| *write != Write::Partial)
+        else {
+            return;
+        };
+        let index = self.clone();
+        let targets = targets.to_vec();
+        tauri::async_runtime::spawn_blocking(move || {
+            for browser in targets {
+                let browser_items: Vec<&Value> = items
+                    .iter()
+                    .filter(|item| {
+                        item.get("browser").and_then(Value::as_str)
+                            == browser.as_ref().map(Browser::name)
+                    })
+                    .collect();
+                if browser_items.is_empty() {
+                    continue;
+                }
+                if let Err(e) = index.write(table, write, &browser, &browser_items) {
+                    eprintln!("Failed to update the local index of {:?}: {}", browser, e);
+                }
+            }
+        });
+    }
+
+    /// Forgets the sync of the tables a successful write `request` made outdated,
+    /// so that they are read from the browser again.
+    pub fn invalidate_for(&self, request: &MozeidonRequest) {
+        for context in request.invalidates() {
+            let table = match context {
+                QueryContext::Bookmarks => Table::Bookmarks,
+                QueryContext::History => Table::History,
+                _ => continue,
+            };
+            if let Err(e) = self.execute("DELETE FROM synced WHERE source = ?1", table.name()) {
+                eprintln!("Failed to invalidate the local index: {}", e);
+            }
+        }
+    }
+
+    /// The last visit time of the newest page of `browser`, if its history was synced.
+    fn newest_visit(&self, browser: &Option<Browser>) -> rusqlite::Result<Option<u64>> {
+        let state = self.state.lock().unwrap();
+        let Some(connection) = state.connection.as_ref() else {
+            return Ok(None);
+        };
+        if !is_synced(connection, Table::History, browser)? {
+            return Ok(None);
+        }
+        connection.query_row(
+            "SELECT MAX(last_visit_time) FROM history WHERE browser = ?1",
+            params![browser_key(browser)],
+            |row| row.get(0),
+        )
+    }
+
+    fn write(
+        &self,
+        table: Table,
+        write: Write,
+        browser: &Option<Browser>,
+        items: &[&Value],
+    ) -> rusqlite::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let Some(connection) = state.connection.as_mut() else {
+            return Ok(());
+        };
+        let key = browser_key(browser);
+        let transaction = connection.transaction()?;
+        if write == Write::Full {
+            transaction.execute(
+                &format!("DELETE FROM {} WHERE browser = ?1", table.name()),
+                params![key],
+            )?;
+        }
+        let since = match write {
+            Write::Diff(since) => Some(since),
+            _ => None,
+        };
+        let count = match table {
+            Table::Bookmarks => insert_bookmarks(&transaction, key, items)?,
+            Table::History => insert_history(&transaction, key, items, since)?,
+        };
+        if write == Write::Full {
+            transaction.execute(
+                "INSERT OR IGNORE INTO synced (browser, source) VALUES (?1, ?2)",
+                params![key, table.name()],
+            )?;
+        }
+        transaction.commit()?;
+        println!(
+            "{} {} of {:?} in the local index",
+            count,
+            table.name(),
+            browser
+        );
+        Ok(())
+    }
+
+    fn forget(&self, browser: &Option<Browser>) -> rusqlite::Result<()> {
+        self.execute(
+            "DELETE FROM synced WHERE browser = ?1",
+            browser_key(browser),
+        )
+    }
+
+    fn execute(&self, sql: &str, param: &str) -> rusqlite::Result<()> {
+        let state = self.state.lock().unwrap();
+        if let Some(connection) = state.connection.as_ref() {
+            connection.execute(sql, params![param])?;
+        }
+        Ok(())
+    }
+
+    /// Runs `f` on a blocking thread, so that a query of the database,
+    /// which holds the lock for as long as it runs, does not hold up the async runtime.
+    async fn blocking<T, F>(&self, f: F) -> Result<T, ClientError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&LocalIndex) -> T + Send + 'static,
+    {
+        let index = self.clone();
+        tauri::async_runtime::spawn_blocking(move || f(&index))
+            .await
+            .map_err(|e| ClientError::Process(format!("Local index: {}", e)))
+    }
+
+    /// `false` if a sync is running already.
+    pub fn start_sync(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.connection.is_some() && !std::mem::replace(&mut state.syncing, true)
+    }
+
+    pub fn end_sync(&self) {
+        self.state.lock().unwrap().syncing = false;
+    }
+}
+
+/// Brings the index of `targets` up to date by reading their bookmarks and history in full again.
+/// The bookmarks replace the indexed ones, while only the pages visited since the newest indexed
+/// one are written, see `Write::Diff`. A browser never synced is written in full.
+pub async fn sync(app: &AppHandle, targets: &[Option<Browser>]) -> Result<(), ClientError> {
+    let index = app.state::<LocalIndex>();
+    let registry = app.state::<QueryRegistry>();
+    for browser in targets {
+        let target = browser.clone();
+        let since = index
+            .blocking(move |index| index.newest_visit(&target))
+            .await?
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to read the local index: {}", e);
+                None
+            });
+        let bookmarks = MozeidonRequest::GetBookmarks {
+            max: None,
+            chunk: None,
+        };
+        let history = MozeidonRequest::GetHistory {
+            max: None,
+            chunk: None,
+        };
+        let writes = [
+            (bookmarks, Table::Bookmarks, Write::Full),
+            (
+                history,
+                Table::History,
+                since.map_or(Write::Full, Write::Diff),
+            ),
+        ];
+        for (request, table, write) in writes {
+            let guard = registry.register_background(&request);
+            let items = fetch(app, &guard, &request, vec![browser.clone()]).await?;
+            let target = browser.clone();
+            index
+                .blocking(move |index| {
+                    index.write(table, write, &target, &items.iter().collect::<Vec<_>>())
+                })
+                .await?
+                .map_err(|e| ClientError::Process(format!("Local index: {}", e)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the bookmarks and the history of `browser`, or of every configured browser,
+/// in full again, e.g. after pages were deleted from the history,
+/// which `sync` does not see as it only writes the pages visited since its newest one.
+#[tauri::command]
+pub async fn resync_local_index(
+    app: AppHandle,
+    cache: State<'_, ResultCache>,
+    index: State<'_, LocalIndex>,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    println!("resync_local_index ({:?})", browser);
+    if !index.start_sync() {
+        return Err(ClientError::Process(
+            "The local index is disabled or syncing already".to_string(),
+        ));
+    }
+    let targets = targets(&app, browser);
+    let forgotten = targets.iter().try_for_each(|browser| index.forget(browser));
+    let result = match forgotten {
+        Ok(()) => sync(&app, &targets).await,
+        Err(e) => Err(ClientError::Process(format!("Local index: {}", e))),
+    };
+    index.end_sync();
+    cache.invalidate(&[QueryContext::Bookmarks, QueryContext::History]);
+    result
+}
+
+fn open_database(dir: &Path) -> Result<Connection, String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(INDEX_FILENAME);
+    let connection = Connection::open(&path)
+        .and_then(|connection| connection.execute_batch(SCHEMA).map(|_| connection))
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    println!("local index at {}", path.display());
+    Ok(connection)
+}
+
+/// The table the items of a read `request` go to, and how.
+fn kept(request: &MozeidonRequest) -> Option<(Table, Write)> {
+    match request {
+        MozeidonRequest::GetBookmarks { max: None, .. } => Some((Table::Bookmarks, Write::Full)),
+        MozeidonRequest::GetHistory { max: None, .. } => Some((Table::History, Write::Full)),
+        MozeidonRequest::GetBookmarks { .. } => Some((Table::Bookmarks, Write::Partial)),
+        MozeidonRequest::GetHistory { .. } => Some((Table::History, Write::Partial)),
+        _ => None,
+    }
+}
+
+fn browser_key(browser: &Option<Browser>) -> &str {
+    browser.as_ref().map_or("", Browser::name)
+}
+
+fn is_synced(
+    connection: &Connection,
+    table: Table,
+    browser: &Option<Browser>,
+) -> rusqlite::Result<bool> {
+    connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM synced WHERE browser = ?1 AND source = ?2)",
+        params![browser_key(browser), table.name()],
+        |row| row.get(0),
+    )
+}
+
+/// The rows of `browser`, the way the CLI prints them:
+/// bookmarks in their order, the most recently visited pages first.
+fn select(
+    connection: &Connection,
+    table: Table,
+    browser: &Option<Browser>,
+    max: u32,
+) -> rusqlite::Result<Vec<Value>> {
+    let key = browser_key(browser);
+    match table {
+        Table::Bookmarks => {
+            let mut statement = connection.prepare_cached(
+                "SELECT url, title, id, parent FROM bookmarks
+                 WHERE browser = ?1 ORDER BY position LIMIT ?2",
+            )?;
+            let rows = statement.query_map(params![key, max], |row| {
+                Ok(BookmarkItem {
+                    url: row.get(0)?,
+                    title: row.get(1)?,
+                    id: row.get(2)?,
+                    parent: row.get(3)?,
+                    browser: browser.clone(),
+                })
+            })?;
+            rows.map(|row| to_value(row?)).collect()
+        }
+        Table::History => {
+            let mut statement = connection.prepare_cached(
+                "SELECT url, title, id, typed_count, visit_count, last_visit_time FROM history
+                 WHERE browser = ?1 ORDER BY last_visit_time DESC LIMIT ?2",
+            )?;
+            let rows = statement.query_map(params![key, max], |row| {
+                Ok(HistoryItem {
+                    url: row.get(0)?,
+                    title: row.get(1)?,
+                    id: row.get(2)?,
+                    typed_count: row.get(3)?,
+                    visit_count: row.get(4)?,
+                    last_visit_time: row.get(5)?,
+                    browser: browser.clone(),
+                })
+            })?;
+            rows.map(|row| to_value(row?)).collect()
+        }
+    }
+}
+
+/// Appended after the bookmarks already there, which are only ever replaced in full.
+/// Returns how many were written.
+fn insert_bookmarks(
+    transaction: &Transaction,
+    key: &str,
+    items: &[&Value],
+) -> rusqlite::Result<usize> {
+    let mut statement = transaction.prepare_cached(
+        "INSERT OR REPLACE INTO bookmarks (browser, position, id, url, title, parent)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    let mut count = 0;
+    for (position, item) in items.iter().enumerate() {
+        let Ok(bookmark) = BookmarkItem::deserialize(*item) else {
+            continue;
+        };
+        statement.execute(params![
+            key,
+            position,
+            bookmark.id,
+            bookmark.url,
+            bookmark.title,
+            bookmark.parent
+        ])?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// A page visited again replaces its row, and any other row of its url.
+/// With `since`, the pages last visited before are skipped, the index holding them already.
+/// Returns how many were written.
+fn insert_history(
+    transaction: &Transaction,
+    key: &str,
+    items: &[&Value],
+    since: Option<u64>,
+) -> rusqlite::Result<usize> {
+    let mut delete_url = transaction
+        .prepare_cached("DELETE FROM history WHERE browser = ?1 AND url = ?2 AND id <> ?3")?;
+    let mut insert = transaction.prepare_cached(
+        "INSERT OR REPLACE INTO history
+         (browser, id, url, title, typed_count, visit_count, last_visit_time)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    let mut count = 0;
+    for item in items {
+        let Ok(page) = HistoryItem::deserialize(*item) else {
+            continue;
+        };
+        if since.is_some_and(|since| page.last_visit_time < since) {
+            continue;
+        }
+        if since.is_some() {
+            delete_url.execute(params![key, page.url, page.id])?;
+        }
+        insert.execute(params![
+            key,
+            page.id,
+            page.url,
+            page.title,
+            page.typed_count,
+            page.visit_count,
+            page.last_visit_time
+        ])?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn to_value<T: serde::Serialize>(item: T) -> rusqlite::Result<Value> {
+    serde_json::to_value(item).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
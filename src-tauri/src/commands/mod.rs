@@ -1,10 +1,39 @@
+mod backend;
+mod bookmarks_file;
+mod browsers;
+mod bulk;
+mod cache;
+mod client;
+mod diagnostics;
+mod everything;
+mod folders;
 mod hide;
+mod history_stats;
 mod host;
 mod init;
-mod models;
+mod local_index;
+pub mod models;
 mod mozeidon;
 mod mozeidon_write;
+mod ndjson;
+mod netscape;
+mod queries;
+mod query;
+mod ranking;
+mod recently_closed;
+mod request;
+mod saved_searches;
+mod search;
 mod show;
+mod simulated;
+mod tab_actions;
+mod versions;
 mod write_manifest;
 
-pub use {hide::*, host::*, init::*, mozeidon::*, mozeidon_write::*, show::*, write_manifest::*};
+pub use simulated::SimulatedBrowser;
+pub use {
+    bookmarks_file::*, bulk::*, cache::*, diagnostics::*, everything::*, folders::*, hide::*,
+    history_stats::*, host::*, init::*, local_index::*, mozeidon::*, mozeidon_write::*, queries::*,
+    ranking::*, recently_closed::*, saved_searches::*, search::*, show::*, tab_actions::*,
+    versions::*, write_manifest::*,
+};
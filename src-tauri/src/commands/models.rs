@@ -1,34 +1,182 @@
+//! The single source of truth for the items printed by `mozeidon-cli`.
+//! `cargo test` generates their TypeScript types into `src/domain/generated`,
+//! and checks them against the CLI outputs in `tests/fixtures/cli`.
+//! The `TS` derives only exist in test builds, `ts-rs` being a dev-dependency.
+
 use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use ts_rs::TS;
+
+use crate::common::Browser;
 
 #[derive(Debug, Deserialize)]
 pub struct Chunk<T> {
     pub data: Vec<T>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(test, derive(TS))]
+#[cfg_attr(test, ts(export, export_to = "../../src/domain/generated/"))]
 pub struct BookmarkItem {
     pub url: String,
+    #[serde(default)]
     pub title: String,
     pub id: String,
+    /// Folder path, e.g. `/Bookmarks Toolbar/dev/`.
+    #[serde(default)]
     pub parent: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional, type = "string"))]
+    pub browser: Option<Browser>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(non_snake_case)]
+/// An open tab, as listed by `tabs get`.
+/// The CLI does not print the tab states, they are `None` unless
+/// a backend knows them, e.g. the simulated browser.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(test, derive(TS))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, ts(export, export_to = "../../src/domain/generated/"))]
 pub struct TabItem {
+    #[cfg_attr(test, ts(type = "number"))]
     pub id: u64,
+    #[serde(default)]
     pub domain: String,
+    #[serde(default)]
     pub title: String,
     pub url: String,
-    pub windowId: u64,
+    #[cfg_attr(test, ts(type = "number"))]
+    pub window_id: u64,
+    /// The selected tab of its window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub active: Option<bool>,
+    /// Milliseconds since the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional, type = "number"))]
+    pub last_accessed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub pinned: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub muted: Option<bool>,
+    /// Unloaded from memory, until switched to again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub discarded: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional, type = "string"))]
+    pub browser: Option<Browser>,
+}
+
+/// Whether a recently closed item is a single tab or a whole window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(test, derive(TS))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, ts(export, export_to = "../../src/domain/generated/"))]
+pub enum ClosedKind {
+    #[default]
+    Tab,
+    Window,
+}
+
+/// A tab or a window closed lately, as listed by `tabs get -c`.
+/// The CLI prints them as tabs, the other fields are only filled in
+/// by backends that know them, e.g. the simulated browser.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(test, derive(TS))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, ts(export, export_to = "../../src/domain/generated/"))]
+pub struct RecentlyClosedItem {
+    /// The id the tab had before it was closed.
+    #[cfg_attr(test, ts(type = "number"))]
+    pub id: u64,
+    /// Handed back to the browser to restore the tab or the window.
+    /// The item cannot be restored without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional))]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub kind: ClosedKind,
+    #[serde(default)]
+    pub domain: String,
+    #[serde(default)]
+    pub title: String,
+    /// For a window, the url of its active tab.
+    pub url: String,
+    /// The window the tab belonged to, or the closed window itself.
+    #[cfg_attr(test, ts(type = "number"))]
+    pub window_id: u64,
+    /// How many tabs a closed window had.
+    #[serde(default = "single_tab")]
+    pub tab_count: u32,
+    /// Milliseconds since the epoch, 0 when unknown.
+    #[serde(default)]
+    #[cfg_attr(test, ts(type = "number"))]
+    pub closed_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional, type = "string"))]
+    pub browser: Option<Browser>,
+}
+
+fn single_tab() -> u32 {
+    1
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// The CLI prints the counters and the visit time under one-letter keys,
+/// which are still accepted as aliases.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(test, derive(TS))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, ts(export, export_to = "../../src/domain/generated/"))]
 pub struct HistoryItem {
     pub url: String,
+    #[serde(default)]
     pub title: String,
     pub id: String,
-    pub tc: u64,
-    pub vc: u64,
-    pub t: u64,
+    /// How many times the url was typed in the address bar.
+    #[serde(default, alias = "tc")]
+    #[cfg_attr(test, ts(type = "number"))]
+    pub typed_count: u64,
+    #[serde(default, alias = "vc")]
+    #[cfg_attr(test, ts(type = "number"))]
+    pub visit_count: u64,
+    /// Milliseconds since the epoch.
+    #[serde(default, alias = "t")]
+    #[cfg_attr(test, ts(type = "number"))]
+    pub last_visit_time: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, ts(optional, type = "string"))]
+    pub browser: Option<Browser>,
+}
+
+/// Items tagged with the browser they were read from,
+/// so that actions on them can be routed back to it.
+pub trait BrowserItem {
+    fn set_browser(&mut self, browser: Browser);
+}
+
+impl BrowserItem for BookmarkItem {
+    fn set_browser(&mut self, browser: Browser) {
+        self.browser = Some(browser);
+    }
+}
+
+impl BrowserItem for TabItem {
+    fn set_browser(&mut self, browser: Browser) {
+        self.browser = Some(browser);
+    }
+}
+
+impl BrowserItem for RecentlyClosedItem {
+    fn set_browser(&mut self, browser: Browser) {
+        self.browser = Some(browser);
+    }
+}
+
+impl BrowserItem for HistoryItem {
+    fn set_browser(&mut self, browser: Browser) {
+        self.browser = Some(browser);
+    }
 }
@@ -1,61 +1,408 @@
-use tauri::AppHandle;
-use tauri_plugin_shell::process::CommandEvent;
-use tauri_plugin_shell::ShellExt;
+use std::sync::{Arc, Mutex};
 
-use crate::commands::models::{BookmarkItem, Chunk, HistoryItem, TabItem};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State};
 
+use crate::commands::browsers::{query_all, stream_all, targets};
+use crate::commands::cache::{CacheKey, CacheMode, Lookup, ResultCache};
+use crate::commands::client::{ClientError, StreamSummary};
+use crate::commands::local_index::{sync, LocalIndex};
+use crate::commands::models::{BookmarkItem, HistoryItem, RecentlyClosedItem, TabItem};
+use crate::commands::ndjson::MalformedLine;
+use crate::commands::queries::{QueryGuard, QueryRegistry};
+use crate::commands::ranking::Ranking;
+use crate::commands::request::{MozeidonRequest, QueryContext, DEFAULT_CHUNK_SIZE};
+use crate::commands::search::SearchIndex;
+use crate::common::Browser;
+
+type CollectedItems = Arc<Mutex<Vec<Value>>>;
+
+/// Reads are sent to `browser`, or to every configured browser and merged,
+/// unless the `ResultCache` still holds their items.
+/// Writes are routed to `browser`, the one the item was read from.
+/// The items read are indexed for `search`, and kept in the `LocalIndex`.
 #[tauri::command]
-pub async fn mozeidon(app: AppHandle, context: String, args: String) -> Result<String, String> {
-    println!("mozeidon {}", args);
-    let args: Vec<&str> = args.split(' ').collect();
-    let sidecar_command = app.shell().sidecar("mozeidon-cli").unwrap();
-    let (mut rx, _) = sidecar_command
-        .args(args)
-        .spawn()
-        .expect("Failed to spawn sidecar");
-
-    let res = tauri::async_runtime::spawn(async move {
-        let mut items = Vec::new();
-        match context.as_str() {
-            "tabs" => {
-                while let Some(event) = rx.recv().await {
-                    if let CommandEvent::Stdout(line_bytes) = event {
-                        let line = String::from_utf8_lossy(&line_bytes);
-                        let json: Chunk<TabItem> = serde_json::from_str(&line).unwrap();
-                        for item in json.data {
-                            items.push(serde_json::to_string(&item).unwrap());
-                        }
-                    }
-                }
+pub async fn mozeidon(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    request: MozeidonRequest,
+    query_id: Option<String>,
+    browser: Option<Browser>,
+) -> Result<String, ClientError> {
+    println!("mozeidon {:?} ({:?})", request, browser);
+    if request.context().is_none() {
+        execute_write(&app, &registry, &cache, &request, query_id, browser).await?;
+        return Ok("[]".to_string());
+    }
+
+    let guard = registry.register(&request, query_id);
+    let items = read(&app, &cache, &guard, &request, targets(&app, browser)).await?;
+    if let Some(context) = request.context() {
+        index_items(&app, context, &items);
+    }
+    to_json_array(&items)
+}
+
+/// The items of a read query against `targets`, from the `ResultCache` if it holds them.
+/// Those of the `LocalIndex` are returned when the CLI fails.
+pub async fn read(
+    app: &AppHandle,
+    cache: &ResultCache,
+    guard: &QueryGuard,
+    request: &MozeidonRequest,
+    targets: Vec<Option<Browser>>,
+) -> Result<Arc<Vec<Value>>, ClientError> {
+    let key = cache.key(request, &targets);
+    let cached_items = match &key {
+        Some(key) => cached(app, cache, key, request).await,
+        None => None,
+    };
+    if let Some(items) = cached_items {
+        return Ok(items);
+    }
+
+    let generation = cache.generation();
+    let items = match fetch(app, guard, request, targets.clone()).await {
+        Ok(items) => Arc::new(items),
+        Err(e) => return indexed_on_failure(app, request, &targets, e).await,
+    };
+    app.state::<LocalIndex>()
+        .store(request, &targets, items.clone());
+    if let Some(key) = key {
+        cache.store(key, items.clone(), generation);
+    }
+    Ok(items)
+}
+
+/// Messages pushed to the webview by `mozeidon_stream`.
+#[derive(Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "camelCase")]
+pub enum StreamEvent {
+    Chunk {
+        items: serde_json::Value,
+    },
+    /// `malformed` lists the lines of output that were skipped.
+    End {
+        count: usize,
+        malformed: Vec<MalformedLine>,
+    },
+    Error(ClientError),
+}
+
+/// Same as `mozeidon`, but each chunk is sent through `on_event`
+/// as soon as it is decoded, followed by an `End` or `Error` message.
+/// Failures are only reported through `on_event`.
+#[tauri::command]
+pub async fn mozeidon_stream(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    request: MozeidonRequest,
+    query_id: Option<String>,
+    browser: Option<Browser>,
+    on_event: Channel<StreamEvent>,
+) -> Result<(), ClientError> {
+    println!("mozeidon_stream {:?} ({:?})", request, browser);
+    let result = match request.context() {
+        Some(context) => {
+            let targets = targets(&app, browser);
+            stream_read(
+                &app, &registry, &cache, &request, context, query_id, targets, &on_event,
+            )
+            .await
+        }
+        None => execute_write(&app, &registry, &cache, &request, query_id, browser)
+            .await
+            .map(|_| StreamSummary::default()),
+    };
+
+    let last_event = match result {
+        Ok(summary) => StreamEvent::End {
+            count: summary.count,
+            malformed: summary.malformed,
+        },
+        Err(e) => StreamEvent::Error(e),
+    };
+    if let Err(e) = on_event.send(last_event) {
+        eprintln!("Failed to send end of stream: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Runs a write request against `browser`,
+/// then drops the cached and indexed items it made outdated.
+pub async fn execute_write(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    request: &MozeidonRequest,
+    query_id: Option<String>,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    execute_batched(app, registry, request, query_id, browser).await?;
+    invalidate_written(app, cache, request);
+    Ok(())
+}
+
+/// Runs one write request of a batch against `browser`, leaving the cached items as they are:
+/// call `invalidate_written` once the batch is done.
+pub async fn execute_batched(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    request: &MozeidonRequest,
+    query_id: Option<String>,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    let (client, _guard) = registry.client(app, request, query_id, browser);
+    client.execute(request).await
+}
+
+/// Drops the cached and indexed items made outdated by writes like `request`.
+pub fn invalidate_written(app: &AppHandle, cache: &ResultCache, request: &MozeidonRequest) {
+    cache.invalidate_for(request);
+    app.state::<LocalIndex>().invalidate_for(request);
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn stream_read(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    request: &MozeidonRequest,
+    context: QueryContext,
+    query_id: Option<String>,
+    targets: Vec<Option<Browser>>,
+    on_event: &Channel<StreamEvent>,
+) -> Result<StreamSummary, ClientError> {
+    let key = cache.key(request, &targets);
+    let cached_items = match &key {
+        Some(key) => cached(app, cache, key, request).await,
+        None => None,
+    };
+    if let Some(items) = cached_items {
+        for chunk in items.chunks(DEFAULT_CHUNK_SIZE as usize) {
+            send_chunk(on_event, Value::Array(chunk.to_vec()));
+        }
+        index_items(app, context, &items);
+        return Ok(StreamSummary {
+            count: items.len(),
+            malformed: Vec::new(),
+        });
+    }
+
+    let generation = cache.generation();
+    let guard = registry.register(request, query_id);
+    let collected = CollectedItems::default();
+    let streamed_targets = targets.clone();
+    let streamed = match context {
+        QueryContext::Tabs => {
+            let on_chunk = chunk_sender::<TabItem>(on_event, collected.clone());
+            stream_all(app, &guard, request, targets, on_chunk).await
+        }
+        QueryContext::RecentlyClosed => {
+            let on_chunk = chunk_sender::<RecentlyClosedItem>(on_event, collected.clone());
+            stream_all(app, &guard, request, targets, on_chunk).await
+        }
+        QueryContext::Bookmarks => {
+            let on_chunk = chunk_sender::<BookmarkItem>(on_event, collected.clone());
+            stream_all(app, &guard, request, targets, on_chunk).await
+        }
+        QueryContext::History => {
+            let on_chunk = chunk_sender::<HistoryItem>(on_event, collected.clone());
+            stream_all(app, &guard, request, targets, on_chunk).await
+        }
+    };
+    let summary = match streamed {
+        Ok(summary) => summary,
+        // Items sent already would be sent twice.
+        Err(e) if !collected.lock().unwrap().is_empty() => return Err(e),
+        Err(e) => {
+            let items = indexed_on_failure(app, request, &streamed_targets, e).await?;
+            for chunk in items.chunks(DEFAULT_CHUNK_SIZE as usize) {
+                send_chunk(on_event, Value::Array(chunk.to_vec()));
             }
-            "bookmarks" => {
-                while let Some(event) = rx.recv().await {
-                    if let CommandEvent::Stdout(line_bytes) = event {
-                        let line = String::from_utf8_lossy(&line_bytes);
-                        let json: Chunk<BookmarkItem> = serde_json::from_str(&line).unwrap();
-                        for item in json.data {
-                            items.push(serde_json::to_string(&item).unwrap());
-                        }
-                    }
-                }
+            index_items(app, context, &items);
+            return Ok(StreamSummary {
+                count: items.len(),
+                malformed: Vec::new(),
+            });
+        }
+    };
+
+    let items = Arc::new(std::mem::take(&mut *collected.lock().unwrap()));
+    index_items(app, context, &items);
+    app.state::<LocalIndex>()
+        .store(request, &streamed_targets, items.clone());
+    if let Some(key) = key {
+        cache.store(key, items, generation);
+    }
+    Ok(summary)
+}
+
+/// Hands the items read for the panel to the `SearchIndex`,
+/// and the history to the `Ranking` as well.
+pub fn index_items(app: &AppHandle, context: QueryContext, items: &Arc<Vec<Value>>) {
+    app.state::<SearchIndex>().update(context, items.clone());
+    if context == QueryContext::History {
+        app.state::<Ranking>().update(items.clone());
+    }
+}
+
+/// The cached items of `key`, if any.
+/// A stale entry is returned as is, and refreshed in the background.
+/// With `CacheMode::StaleWhileRevalidate`, a miss is answered from the `LocalIndex`
+/// and the index is synced in the background. With `CacheMode::Fresh`, it goes to the browser.
+async fn cached(
+    app: &AppHandle,
+    cache: &ResultCache,
+    key: &CacheKey,
+    request: &MozeidonRequest,
+) -> Option<Arc<Vec<Value>>> {
+    match cache.lookup(key) {
+        Lookup::Hit(items) => {
+            println!("{} items from cache", items.len());
+            Some(items)
+        }
+        Lookup::Stale(items) => {
+            println!("{} stale items from cache, refreshing", items.len());
+            refresh(app.clone(), key.clone(), request.clone());
+            Some(items)
+        }
+        Lookup::Miss if cache.mode() == CacheMode::Fresh => None,
+        Lookup::Miss => {
+            let items = app
+                .state::<LocalIndex>()
+                .items(request, key.targets())
+                .await?;
+            println!("{} items from the local index, syncing", items.len());
+            sync_index(app.clone(), key.clone(), request.clone());
+            Some(items)
+        }
+    }
+}
+
+/// The items of the `LocalIndex` for a read `request` the CLI failed with `error`,
+/// or `error` if the index does not hold them all. A cancelled read is not answered.
+async fn indexed_on_failure(
+    app: &AppHandle,
+    request: &MozeidonRequest,
+    targets: &[Option<Browser>],
+    error: ClientError,
+) -> Result<Arc<Vec<Value>>, ClientError> {
+    if matches!(error, ClientError::Cancelled) {
+        return Err(error);
+    }
+    match app.state::<LocalIndex>().items(request, targets).await {
+        Some(items) => {
+            eprintln!("{}, answering from the local index", error);
+            Ok(items)
+        }
+        None => Err(error),
+    }
+}
+
+fn refresh(app: AppHandle, key: CacheKey, request: MozeidonRequest) {
+    tauri::async_runtime::spawn(async move {
+        let cache = app.state::<ResultCache>();
+        let generation = cache.generation();
+        let guard = app.state::<QueryRegistry>().register_background(&request);
+        match fetch(&app, &guard, &request, key.targets().to_vec()).await {
+            Ok(items) => {
+                let items = Arc::new(items);
+                app.state::<LocalIndex>()
+                    .store(&request, key.targets(), items.clone());
+                cache.store(key, items, generation);
             }
-            "history" => {
-                while let Some(event) = rx.recv().await {
-                    if let CommandEvent::Stdout(line_bytes) = event {
-                        let line = String::from_utf8_lossy(&line_bytes);
-                        let json: Chunk<HistoryItem> = serde_json::from_str(&line).unwrap();
-                        for item in json.data {
-                            items.push(serde_json::to_string(&item).unwrap());
-                        }
-                    }
+            Err(e) => {
+                eprintln!("Failed to refresh cached {:?}: {}", request.context(), e);
+                cache.refresh_failed(&key);
+            }
+        }
+    });
+}
+
+/// Brings the `LocalIndex` up to date, then caches what it holds for `key`.
+fn sync_index(app: AppHandle, key: CacheKey, request: MozeidonRequest) {
+    if !app.state::<LocalIndex>().start_sync() {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let index = app.state::<LocalIndex>();
+        let cache = app.state::<ResultCache>();
+        let generation = cache.generation();
+        match sync(&app, key.targets()).await {
+            Ok(()) => {
+                if let Some(items) = index.items(&request, key.targets()).await {
+                    cache.store(key, items, generation);
                 }
             }
-            _ => {}
+            Err(e) => eprintln!("Failed to sync the local index: {}", e),
         }
-        format!("[{}]", items.join(","))
-    })
-    .await
-    .map_err(|e| format!("Failed to parse mozeidon output: {}", e));
+        index.end_sync();
+    });
+}
+
+/// Runs a read query against `targets`, and returns its items the way they are cached.
+pub async fn fetch(
+    app: &AppHandle,
+    guard: &QueryGuard,
+    request: &MozeidonRequest,
+    targets: Vec<Option<Browser>>,
+) -> Result<Vec<Value>, ClientError> {
+    match request.context() {
+        Some(QueryContext::Tabs) => {
+            to_values(query_all::<TabItem>(app, guard, request, targets).await?)
+        }
+        Some(QueryContext::RecentlyClosed) => {
+            to_values(query_all::<RecentlyClosedItem>(app, guard, request, targets).await?)
+        }
+        Some(QueryContext::Bookmarks) => {
+            to_values(query_all::<BookmarkItem>(app, guard, request, targets).await?)
+        }
+        Some(QueryContext::History) => {
+            to_values(query_all::<HistoryItem>(app, guard, request, targets).await?)
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Sends each chunk through `on_event`, whichever browser it comes from,
+/// and keeps a copy of its items in `collected`.
+fn chunk_sender<T: Serialize>(
+    on_event: &Channel<StreamEvent>,
+    collected: CollectedItems,
+) -> impl FnMut(Vec<T>) + Clone + Send + 'static {
+    let on_event = on_event.clone();
+    move |items| match to_values(items) {
+        Ok(items) => {
+            collected.lock().unwrap().extend(items.iter().cloned());
+            send_chunk(&on_event, Value::Array(items));
+        }
+        Err(e) => {
+            if let Err(e) = on_event.send(StreamEvent::Error(e)) {
+                eprintln!("Failed to send chunk: {}", e);
+            }
+        }
+    }
+}
+
+fn send_chunk(on_event: &Channel<StreamEvent>, items: Value) {
+    if let Err(e) = on_event.send(StreamEvent::Chunk { items }) {
+        eprintln!("Failed to send chunk: {}", e);
+    }
+}
+
+fn to_values<T: Serialize>(items: Vec<T>) -> Result<Vec<Value>, ClientError> {
+    Ok(items
+        .into_iter()
+        .map(serde_json::to_value)
+        .collect::<Result<_, _>>()?)
+}
 
-    res
+fn to_json_array(items: &[Value]) -> Result<String, ClientError> {
+    Ok(serde_json::to_string(items)?)
 }
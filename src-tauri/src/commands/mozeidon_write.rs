@@ -1,27 +1,21 @@
-use tauri::AppHandle;
-use tauri_plugin_shell::process::CommandEvent;
-use tauri_plugin_shell::ShellExt;
+use tauri::{AppHandle, State};
 
-#[tauri::command]
-pub async fn mozeidon_write(app: AppHandle, args: Vec<String>) -> Result<String, String> {
-    println!("mozeidon_write {:?}", args);
-    let sidecar_command = app.shell().sidecar("mozeidon-cli").unwrap();
-    let (mut rx, _) = sidecar_command
-        .args(args)
-        .spawn()
-        .expect("Failed to spawn sidecar");
-
-    let res = tauri::async_runtime::spawn(async move {
-        while let Some(event) = rx.recv().await {
-            if let CommandEvent::Stdout(line_bytes) = event {
-                let line = String::from_utf8_lossy(&line_bytes);
-                println!("OUTPUT: {}", line);
-            }
-        }
-        format!("[]")
-    })
-    .await
-    .map_err(|e| format!("Failed to parse mozeidon output: {}", e));
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::mozeidon::execute_write;
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::MozeidonRequest;
+use crate::common::Browser;
 
-    res
+#[tauri::command]
+pub async fn mozeidon_write(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    request: MozeidonRequest,
+    browser: Option<Browser>,
+) -> Result<String, ClientError> {
+    println!("mozeidon_write {:?} ({:?})", request, browser);
+    execute_write(&app, &registry, &cache, &request, None, browser).await?;
+    Ok("[]".to_string())
 }
@@ -0,0 +1,174 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::common::Browser;
+
+/// How much of a malformed line is kept, to recognise e.g. a log message.
+const EXCERPT_LENGTH: usize = 80;
+
+/// A complete line of output, numbered from 1.
+pub struct Line {
+    pub number: usize,
+    pub text: String,
+}
+
+/// A line that could not be decoded. It is skipped, and the rest of the output kept.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MalformedLine {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser: Option<Browser>,
+    pub line: usize,
+    pub error: String,
+    pub excerpt: String,
+}
+
+impl fmt::Display for MalformedLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} ({:?})", self.line, self.error, self.excerpt)
+    }
+}
+
+/// Splits newline-delimited output, read in pieces of any size, into lines.
+/// A partial line is held on to until the rest of it is read.
+#[derive(Default)]
+pub struct LineDecoder {
+    buffer: Vec<u8>,
+    lines_read: usize,
+}
+
+impl LineDecoder {
+    /// The lines completed by `bytes`. Blank lines are dropped, but still counted.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Line> {
+        self.buffer.extend_from_slice(bytes);
+        let Some(last_newline) = self.buffer.iter().rposition(|b| *b == b'\n') else {
+            return Vec::new();
+        };
+        let rest = self.buffer.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.buffer, rest);
+        complete[..last_newline]
+            .split(|b| *b == b'\n')
+            .filter_map(|bytes| self.line(bytes))
+            .collect()
+    }
+
+    /// The last line, when the output does not end with a newline.
+    pub fn finish(&mut self) -> Option<Line> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let rest = std::mem::take(&mut self.buffer);
+        self.line(&rest)
+    }
+
+    fn line(&mut self, bytes: &[u8]) -> Option<Line> {
+        self.lines_read += 1;
+        let text = String::from_utf8_lossy(bytes);
+        if text.trim().is_empty() {
+            return None;
+        }
+        Some(Line {
+            number: self.lines_read,
+            text: text.trim_end().to_string(),
+        })
+    }
+}
+
+/// Decodes `line` as a JSON document, or tells where and why it could not be.
+pub fn decode<T: DeserializeOwned>(line: &Line) -> Result<T, MalformedLine> {
+    serde_json::from_str(&line.text).map_err(|e| MalformedLine {
+        browser: None,
+        line: line.number,
+        error: e.to_string(),
+        excerpt: line.text.chars().take(EXCERPT_LENGTH).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: Vec<Line>) -> Vec<(usize, String)> {
+        lines
+            .into_iter()
+            .map(|line| (line.number, line.text))
+            .collect()
+    }
+
+    #[test]
+    fn lines_split_across_chunks_are_joined() {
+        let mut decoder = LineDecoder::default();
+        assert!(decoder.push(br#"{"title":"caf"#).is_empty());
+        // `é` itself is split between two chunks.
+        assert!(decoder.push(&[0xc3]).is_empty());
+        assert_eq!(
+            texts(decoder.push(b"\xa9\"}\n{\"title\":")),
+            [(1, r#"{"title":"café"}"#.to_string())]
+        );
+        assert_eq!(
+            texts(decoder.push(b"\"tea\"}\n")),
+            [(2, r#"{"title":"tea"}"#.to_string())]
+        );
+        assert!(decoder.finish().is_none());
+    }
+
+    #[test]
+    fn crlf_endings_are_trimmed() {
+        let mut decoder = LineDecoder::default();
+        assert_eq!(
+            texts(decoder.push(b"one\r\ntwo\r")),
+            [(1, "one".to_string())]
+        );
+        assert_eq!(
+            texts(decoder.push(b"\nthree\r\n")),
+            [(2, "two".to_string()), (3, "three".to_string())]
+        );
+    }
+
+    #[test]
+    fn blank_lines_are_dropped_but_counted() {
+        let mut decoder = LineDecoder::default();
+        assert_eq!(
+            texts(decoder.push(b"one\n\n  \r\ntwo\n")),
+            [(1, "one".to_string()), (4, "two".to_string())]
+        );
+    }
+
+    #[test]
+    fn a_trailing_partial_line_is_kept_until_the_end() {
+        let mut decoder = LineDecoder::default();
+        assert_eq!(texts(decoder.push(b"one\ntw")), [(1, "one".to_string())]);
+        let last = decoder.finish().unwrap();
+        assert_eq!((last.number, last.text.as_str()), (2, "tw"));
+        assert!(decoder.finish().is_none());
+
+        let mut decoder = LineDecoder::default();
+        assert_eq!(texts(decoder.push(b"one\n  ")), [(1, "one".to_string())]);
+        assert!(decoder.finish().is_none());
+    }
+
+    #[test]
+    fn malformed_lines_tell_where_and_why() {
+        let line = Line {
+            number: 3,
+            text: format!("warning: {}", "x".repeat(100)),
+        };
+        let malformed = decode::<serde_json::Value>(&line).unwrap_err();
+        assert_eq!(malformed.line, 3);
+        assert!(malformed.error.contains("line 1 column 1"));
+        assert_eq!(malformed.excerpt.chars().count(), EXCERPT_LENGTH);
+        assert!(malformed.excerpt.starts_with("warning: "));
+        assert!(malformed.to_string().starts_with("line 3: expected value"));
+
+        let line = Line {
+            number: 1,
+            text: r#"{"id":1}"#.to_string(),
+        };
+        assert_eq!(
+            decode::<serde_json::Value>(&line).unwrap(),
+            serde_json::json!({ "id": 1 })
+        );
+    }
+}
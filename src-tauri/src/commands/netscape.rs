@@ -0,0 +1,354 @@
+//! The Netscape bookmark file format, the `bookmarks.html` every browser imports and exports.
+//! Folders are nested `<DL>` lists titled by the `<H3>` before them,
+//! bookmarks are `<A HREF>` links.
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::models::BookmarkItem;
+
+const HEADER: &str = "<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+";
+
+/// A bookmark read from a bookmark file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportedBookmark {
+    pub title: String,
+    pub url: String,
+    /// Folder path, e.g. `/Bookmarks Toolbar/dev/`.
+    pub folder: String,
+}
+
+/// The content of a bookmark file.
+#[derive(Debug, Default)]
+pub struct BookmarkFile {
+    /// Every folder path, parents first, including the folders without bookmarks.
+    pub folders: Vec<String>,
+    pub bookmarks: Vec<ImportedBookmark>,
+}
+
+#[derive(Default)]
+struct Folder<'a> {
+    name: &'a str,
+    folders: Vec<Folder<'a>>,
+    bookmarks: Vec<&'a BookmarkItem>,
+}
+
+impl<'a> Folder<'a> {
+    fn add(&mut self, bookmark: &'a BookmarkItem) {
+        let mut folder = self;
+        for name in bookmark.parent.split('/').filter(|name| !name.is_empty()) {
+            let position = match folder.folders.iter().position(|f| f.name == name) {
+                Some(position) => position,
+                None => {
+                    folder.folders.push(Folder {
+                        name,
+                        ..Default::default()
+                    });
+                    folder.folders.len() - 1
+                }
+            };
+            folder = &mut folder.folders[position];
+        }
+        folder.bookmarks.push(bookmark);
+    }
+
+    fn write(&self, html: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth);
+        html.push_str(&format!("{}<DL><p>\n", indent));
+        for folder in &self.folders {
+            html.push_str(&format!(
+                "{}    <DT><H3>{}</H3>\n",
+                indent,
+                escape(folder.name)
+            ));
+            folder.write(html, depth + 1);
+        }
+        for bookmark in &self.bookmarks {
+            html.push_str(&format!(
+                "{}    <DT><A HREF=\"{}\">{}</A>\n",
+                indent,
+                escape(&bookmark.url),
+                escape(&bookmark.title)
+            ));
+        }
+        html.push_str(&format!("{}</DL><p>\n", indent));
+    }
+}
+
+/// Writes `bookmarks` as a bookmark file, their folders as nested lists.
+pub fn export(bookmarks: &[BookmarkItem]) -> String {
+    let mut root = Folder::default();
+    for bookmark in bookmarks {
+        root.add(bookmark);
+    }
+    let mut html = HEADER.to_string();
+    root.write(&mut html, 0);
+    html
+}
+
+/// Reads the folders and bookmarks of a bookmark file.
+/// Unknown tags are ignored, so files of any browser are accepted.
+/// A `/` in a folder name is replaced by `-`, as it separates folders in paths.
+/// `None` if `html` holds no `<DL>` list, so is no bookmark file.
+pub fn parse(html: &str) -> Option<BookmarkFile> {
+    let mut file = BookmarkFile::default();
+    let mut listed = false;
+    // One entry per open `<DL>`: the folder it lists, `None` for the outermost one.
+    let mut open_lists: Vec<Option<String>> = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        let name = tag
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        match name.as_str() {
+            "H3" => heading = Some(text_until(&mut rest, "</H3").replace('/', "-")),
+            "DL" => {
+                listed = true;
+                open_lists.push(heading.take());
+                if open_lists.last().is_some_and(Option::is_some) {
+                    file.folders.push(current_folder(&open_lists));
+                }
+            }
+            "/DL" => {
+                open_lists.pop();
+            }
+            "A" => {
+                let title = text_until(&mut rest, "</A");
+                let Some(url) = attribute(tag, "HREF").filter(|url| !url.is_empty()) else {
+                    continue;
+                };
+                file.bookmarks.push(ImportedBookmark {
+                    title,
+                    url,
+                    folder: current_folder(&open_lists),
+                });
+            }
+            _ => {}
+        }
+    }
+    listed.then_some(file)
+}
+
+/// The folder path of the innermost open list.
+fn current_folder(open_lists: &[Option<String>]) -> String {
+    let names: String = open_lists
+        .iter()
+        .flatten()
+        .filter(|name| !name.is_empty())
+        .map(|name| format!("/{}", name))
+        .collect();
+    format!("{}/", names)
+}
+
+/// The unescaped text before the closing tag `closing`, which `rest` is moved past.
+fn text_until(rest: &mut &str, closing: &str) -> String {
+    let end = find_ignore_case(rest, closing).unwrap_or(rest.len());
+    let text = unescape(rest[..end].trim());
+    *rest = &rest[end..];
+    text
+}
+
+/// The unescaped value of the attribute `name` of `tag`, quoted or not.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = find_ignore_case(tag, &format!("{}=", name))? + name.len() + 1;
+    let value = &tag[start..];
+    let value = match value.chars().next()? {
+        quote @ ('"' | '\'') => value[1..].split(quote).next()?,
+        _ => value.split_whitespace().next()?,
+    };
+    Some(unescape(value))
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .to_ascii_uppercase()
+        .find(&needle.to_ascii_uppercase())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((end, decode_entity(&rest[1..end])?)));
+        match entity {
+            Some((end, c)) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(title: &str, url: &str, folder: &str) -> ImportedBookmark {
+        ImportedBookmark {
+            title: title.to_string(),
+            url: url.to_string(),
+            folder: folder.to_string(),
+        }
+    }
+
+    #[test]
+    fn nested_lists_are_folder_paths() {
+        let file = parse(
+            r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><H3 ADD_DATE="1">Toolbar</H3>
+    <DL><p>
+        <DT><H3>dev</H3>
+        <DL><p>
+            <DT><A HREF="https://docs.rs">Docs</A>
+        </DL><p>
+        <DT><h3>empty</h3>
+        <dl><p>
+        </dl><p>
+        <DT><A HREF="https://news.ycombinator.com" ADD_DATE="2">HN</A>
+    </DL><p>
+    <DT><H3>a/b</H3>
+    <DL><p>
+    </DL><p>
+    <DT><A HREF='https://example.com'>Root</A>
+</DL><p>
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            file.folders,
+            ["/Toolbar/", "/Toolbar/dev/", "/Toolbar/empty/", "/a-b/"]
+        );
+        assert_eq!(
+            file.bookmarks,
+            [
+                bookmark("Docs", "https://docs.rs", "/Toolbar/dev/"),
+                bookmark("HN", "https://news.ycombinator.com", "/Toolbar/"),
+                bookmark("Root", "https://example.com", "/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        let file = parse(
+            r#"<DL><p>
+    <DT><H3>Tom &amp; Jerry</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/?a=1&amp;b=2">&lt;b&gt; &quot;x&quot; &#39;y&#x27; &#x263A; &unknown; a & b</A>
+    </DL><p>
+</DL><p>"#,
+        )
+        .unwrap();
+        assert_eq!(file.folders, ["/Tom & Jerry/"]);
+        assert_eq!(
+            file.bookmarks,
+            [bookmark(
+                "<b> \"x\" 'y' ☺ &unknown; a & b",
+                "https://example.com/?a=1&b=2",
+                "/Tom & Jerry/"
+            )]
+        );
+    }
+
+    #[test]
+    fn links_without_href_are_skipped() {
+        let file = parse(
+            r#"<DL><p>
+    <DT><A NAME="anchor">No link</A>
+    <DT><A HREF="">Empty link</A>
+    <DT><A HREF=https://example.com>Unquoted</A>
+</DL><p>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            file.bookmarks,
+            [bookmark("Unquoted", "https://example.com", "/")]
+        );
+    }
+
+    #[test]
+    fn exported_files_read_back() {
+        let item = |title: &str, url: &str, parent: &str| BookmarkItem {
+            url: url.to_string(),
+            title: title.to_string(),
+            id: String::new(),
+            parent: parent.to_string(),
+            browser: None,
+        };
+        let file = parse(&export(&[
+            item("Docs & more", "https://docs.rs/?a=1&b=2", "/Toolbar/dev/"),
+            item("<Root>", "https://example.com", "/"),
+        ]))
+        .unwrap();
+        assert_eq!(file.folders, ["/Toolbar/", "/Toolbar/dev/"]);
+        assert_eq!(
+            file.bookmarks,
+            [
+                bookmark("Docs & more", "https://docs.rs/?a=1&b=2", "/Toolbar/dev/"),
+                bookmark("<Root>", "https://example.com", "/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn files_without_a_list_are_rejected() {
+        assert!(parse("<html><body><a href=\"https://example.com\">x</a></body></html>").is_none());
+        assert!(parse("").is_none());
+        assert!(parse("<DL><p>\n</DL><p>").is_some_and(|file| file.bookmarks.is_empty()));
+    }
+}
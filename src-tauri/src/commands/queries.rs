@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tauri::{AppHandle, State};
+use tokio_util::sync::CancellationToken;
+
+use crate::commands::client::MozeidonClient;
+use crate::commands::request::{MozeidonRequest, QueryContext};
+use crate::common::Browser;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default timeouts per request type, keyed like the serialized `MozeidonRequest` tag.
+/// Big reads get more time than interactive tab actions.
+const DEFAULT_TIMEOUTS: &[(&str, Duration)] = &[
+    ("getTabs", Duration::from_secs(5)),
+    ("getRecentlyClosed", Duration::from_secs(5)),
+    ("getBookmarks", Duration::from_secs(30)),
+    ("getHistory", Duration::from_secs(60)),
+    ("getExtensionVersion", Duration::from_secs(5)),
+];
+
+struct InFlightQuery {
+    id: u64,
+    query_id: Option<String>,
+    context: Option<QueryContext>,
+    token: CancellationToken,
+}
+
+#[derive(Default)]
+struct RegistryState {
+    next_id: u64,
+    in_flight: Vec<InFlightQuery>,
+    timeouts: HashMap<String, Duration>,
+}
+
+/// Keeps track of in-flight sidecar queries,
+/// so they can be timed out, cancelled or superseded.
+#[derive(Default, Clone)]
+pub struct QueryRegistry {
+    state: Arc<Mutex<RegistryState>>,
+}
+
+/// Returned by `QueryRegistry::register`, unregisters the query when dropped.
+pub struct QueryGuard {
+    id: u64,
+    token: CancellationToken,
+    timeout: Duration,
+    registry: QueryRegistry,
+}
+
+impl QueryGuard {
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// A client bound to the query's timeout and cancellation token, targeting `browser`.
+    /// A query fanned out to several browsers gets one client per browser.
+    pub fn client(&self, app: &AppHandle, browser: Option<Browser>) -> MozeidonClient {
+        MozeidonClient::new(app)
+            .with_browser(browser)
+            .with_timeout(self.timeout)
+            .with_cancellation(self.token())
+    }
+}
+
+impl Drop for QueryGuard {
+    fn drop(&mut self) {
+        let mut state = self.registry.state.lock().unwrap();
+        state.in_flight.retain(|query| query.id != self.id);
+    }
+}
+
+impl QueryRegistry {
+    /// Registers a new query.
+    /// A read query cancels any in-flight query of the same context,
+    /// since the panel only ever shows the latest one.
+    pub fn register(&self, request: &MozeidonRequest, query_id: Option<String>) -> QueryGuard {
+        self.register_as(request, query_id, request.context())
+    }
+
+    /// Registers a query the panel is not waiting for, e.g. a cache refresh.
+    /// It is neither superseded by panel queries nor cancelled when the panel is hidden.
+    pub fn register_background(&self, request: &MozeidonRequest) -> QueryGuard {
+        self.register_as(request, None, None)
+    }
+
+    fn register_as(
+        &self,
+        request: &MozeidonRequest,
+        query_id: Option<String>,
+        context: Option<QueryContext>,
+    ) -> QueryGuard {
+        let token = CancellationToken::new();
+        let timeout = self.timeout_for(request);
+
+        let mut state = self.state.lock().unwrap();
+        if context.is_some() {
+            for query in state.in_flight.iter().filter(|q| q.context == context) {
+                println!("cancelling superseded query {:?}", query.context);
+                query.token.cancel();
+            }
+        }
+        state.next_id += 1;
+        let id = state.next_id;
+        state.in_flight.push(InFlightQuery {
+            id,
+            query_id,
+            context,
+            token: token.clone(),
+        });
+
+        QueryGuard {
+            id,
+            token,
+            timeout,
+            registry: self.clone(),
+        }
+    }
+
+    /// Registers `request` and returns a client bound to its timeout and cancellation token,
+    /// targeting `browser`.
+    /// Keep the guard alive until the client is done.
+    pub fn client(
+        &self,
+        app: &AppHandle,
+        request: &MozeidonRequest,
+        query_id: Option<String>,
+        browser: Option<Browser>,
+    ) -> (MozeidonClient, QueryGuard) {
+        let guard = self.register(request, query_id);
+        let client = guard.client(app, browser);
+        (client, guard)
+    }
+
+    /// Cancels the query registered with `query_id`.
+    /// Returns whether such a query was in flight.
+    pub fn cancel(&self, query_id: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        let mut found = false;
+        for query in state
+            .in_flight
+            .iter()
+            .filter(|q| q.query_id.as_deref() == Some(query_id))
+        {
+            query.token.cancel();
+            found = true;
+        }
+        found
+    }
+
+    /// Cancels every in-flight read query, e.g. when the panel is hidden.
+    pub fn cancel_reads(&self) {
+        let state = self.state.lock().unwrap();
+        for query in state.in_flight.iter().filter(|q| q.context.is_some()) {
+            query.token.cancel();
+        }
+    }
+
+    pub fn timeout_for(&self, request: &MozeidonRequest) -> Duration {
+        let state = self.state.lock().unwrap();
+        state
+            .timeouts
+            .get(request.name())
+            .copied()
+            .or_else(|| {
+                DEFAULT_TIMEOUTS
+                    .iter()
+                    .find(|(name, _)| *name == request.name())
+                    .map(|(_, timeout)| *timeout)
+            })
+            .unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    pub fn set_timeouts(&self, timeouts: HashMap<String, Duration>) {
+        self.state.lock().unwrap().timeouts.extend(timeouts);
+    }
+}
+
+#[tauri::command]
+pub fn cancel_query(registry: State<'_, QueryRegistry>, query_id: String) -> bool {
+    println!("cancel_query {}", query_id);
+    registry.cancel(&query_id)
+}
+
+/// Overrides the default timeout of some request types, in milliseconds.
+#[tauri::command]
+pub fn set_query_timeouts(registry: State<'_, QueryRegistry>, timeouts_ms: HashMap<String, u64>) {
+    println!("set_query_timeouts {:?}", timeouts_ms);
+    registry.set_timeouts(
+        timeouts_ms
+            .into_iter()
+            .map(|(name, ms)| (name, Duration::from_millis(ms)))
+            .collect(),
+    );
+}
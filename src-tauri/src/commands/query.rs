@@ -0,0 +1,442 @@
+//! The query language of the search box: words and `"quoted phrases"` matched against
+//! the searched fields, `field:value` filters, and `-` before a term to exclude what it matches.
+//! `domain:github.com`, `title:`, `url:`, `folder:` (bookmarks), `window:` (tabs),
+//! `after:2025-01-01` and `before:` (history).
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::commands::request::QueryContext;
+use crate::common::{date, domain_of};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// A term of the query that could not be understood, and was left out of the search.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryDiagnostic {
+    pub message: String,
+    /// `[start, end)` offsets of the term in the query, in UTF-16 code units.
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// The domain of the url, or one of its subdomains.
+    Domain(String),
+    Title(String),
+    Url(String),
+    /// Part of the folder path of a bookmark.
+    Folder(String),
+    Window(u64),
+    /// Last visited on that day, counted from 1970-01-01, or later.
+    After(i64),
+    /// Last visited before that day.
+    Before(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TermKind {
+    /// Matched fuzzily or exactly, as the search type says.
+    Word(String),
+    /// Part of one of the searched fields, ignoring case.
+    Phrase(String),
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub negated: bool,
+    pub kind: TermKind,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedQuery {
+    pub terms: Vec<Term>,
+    pub diagnostics: Vec<QueryDiagnostic>,
+}
+
+impl ParsedQuery {
+    /// The words to match, without the negated ones.
+    pub fn text(&self) -> String {
+        self.terms
+            .iter()
+            .filter(|term| !term.negated)
+            .filter_map(|term| match &term.kind {
+                TermKind::Word(word) => Some(word.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The phrases an item must contain, lowercased.
+    pub fn phrases(&self) -> Vec<String> {
+        self.terms
+            .iter()
+            .filter(|term| !term.negated)
+            .filter_map(|term| match &term.kind {
+                TermKind::Phrase(phrase) => Some(phrase.to_lowercase()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether `item` passes the filters and none of the negated terms matches it.
+    /// `fields` are its searched fields, lowercased.
+    pub fn accepts(&self, item: &Value, fields: &[String], utc_offset_minutes: i32) -> bool {
+        self.terms.iter().all(|term| {
+            let matches = match &term.kind {
+                TermKind::Filter(filter) => filter.matches(item, utc_offset_minutes),
+                TermKind::Word(text) | TermKind::Phrase(text) if term.negated => {
+                    let text = text.to_lowercase();
+                    fields.iter().any(|field| field.contains(&text))
+                }
+                // Matched by the search itself.
+                TermKind::Word(_) | TermKind::Phrase(_) => return true,
+            };
+            matches != term.negated
+        })
+    }
+}
+
+impl Filter {
+    fn matches(&self, item: &Value, utc_offset_minutes: i32) -> bool {
+        let text = |field: &str| {
+            item.get(field)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_lowercase()
+        };
+        let number = |field: &str| item.get(field).and_then(Value::as_u64).unwrap_or_default();
+        let last_visit_time = number("lastVisitTime") as i64;
+        let day_start = |day: i64| day * DAY_MS - utc_offset_minutes as i64 * 60 * 1000;
+        match self {
+            Filter::Domain(domain) => {
+                let item_domain = domain_of(&text("url"));
+                item_domain == *domain || item_domain.ends_with(&format!(".{}", domain))
+            }
+            Filter::Title(title) => text("title").contains(title),
+            Filter::Url(url) => text("url").contains(url),
+            Filter::Folder(folder) => text("parent").contains(folder),
+            Filter::Window(window_id) => number("windowId") == *window_id,
+            Filter::After(day) => last_visit_time >= day_start(*day),
+            Filter::Before(day) => last_visit_time < day_start(*day),
+        }
+    }
+
+    /// The contexts whose items have the filtered field.
+    fn applies_to(&self, context: QueryContext) -> Result<(), &'static str> {
+        match self {
+            Filter::Folder(_) if context != QueryContext::Bookmarks => {
+                Err("folder: only applies to bookmarks")
+            }
+            Filter::Window(_)
+                if !matches!(context, QueryContext::Tabs | QueryContext::RecentlyClosed) =>
+            {
+                Err("window: only applies to tabs")
+            }
+            Filter::After(_) | Filter::Before(_) if context != QueryContext::History => {
+                Err("after: and before: only apply to history")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Splits `query` into terms. Terms that cannot be understood, or do not apply to
+/// the items of `context`, are reported and left out, so that the rest of the query still works.
+/// An unknown `key:value` is a word, as in `https://`.
+pub fn parse(query: &str, context: QueryContext) -> ParsedQuery {
+    let mut parsed = ParsedQuery::default();
+    let mut position = 0;
+    while let Some(start) = next_non_space(query, position) {
+        let mut cursor = start;
+        let negated = query[cursor..].starts_with('-')
+            && query[cursor + 1..]
+                .chars()
+                .next()
+                .is_some_and(|c| !c.is_whitespace());
+        if negated {
+            cursor += 1;
+        }
+
+        let (kind, end) = if query[cursor..].starts_with('"') {
+            let (phrase, end) = quoted(query, cursor, &mut parsed.diagnostics);
+            (Ok(TermKind::Phrase(phrase)), end)
+        } else {
+            let word_end = word_end(query, cursor);
+            let word = &query[cursor..word_end];
+            match word.split_once(':').filter(|(key, _)| is_filter(key)) {
+                Some((key, value)) if value.is_empty() && query[word_end..].starts_with('"') => {
+                    let (value, end) = quoted(query, word_end, &mut parsed.diagnostics);
+                    (filter(key, &value), end)
+                }
+                Some((key, value)) => (filter(key, value), word_end),
+                None => (Ok(TermKind::Word(word.to_string())), word_end),
+            }
+        };
+        position = end;
+
+        let kind = kind.and_then(|kind| {
+            if let TermKind::Filter(filter) = &kind {
+                filter.applies_to(context)?;
+            }
+            Ok(kind)
+        });
+        match kind {
+            Ok(TermKind::Word(word) | TermKind::Phrase(word)) if word.is_empty() => {}
+            Ok(kind) => parsed.terms.push(Term { negated, kind }),
+            Err(message) => parsed
+                .diagnostics
+                .push(diagnostic(query, start, end, message)),
+        }
+    }
+    parsed
+}
+
+fn is_filter(key: &str) -> bool {
+    matches!(
+        key.to_lowercase().as_str(),
+        "domain" | "title" | "url" | "folder" | "window" | "after" | "before"
+    )
+}
+
+fn filter(key: &str, value: &str) -> Result<TermKind, String> {
+    let key = key.to_lowercase();
+    if value.is_empty() {
+        return Err(format!("{}: needs a value", key));
+    }
+    let value = value.to_lowercase();
+    let filter = match key.as_str() {
+        "domain" => Filter::Domain(value.trim_start_matches("www.").to_string()),
+        "title" => Filter::Title(value),
+        "url" => Filter::Url(value),
+        "folder" => Filter::Folder(value),
+        "window" => Filter::Window(
+            value
+                .parse()
+                .map_err(|_| format!("window: expects a window id, not {}", value))?,
+        ),
+        "after" => Filter::After(day(&value).ok_or("after: expects a date like 2025-01-01")?),
+        _ => Filter::Before(day(&value).ok_or("before: expects a date like 2025-01-01")?),
+    };
+    Ok(TermKind::Filter(filter))
+}
+
+/// The day of a `YYYY-MM-DD` date, counted from 1970-01-01.
+fn day(value: &str) -> Option<i64> {
+    let mut parts = value.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let civil_year = year;
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    // Rejects the days a month does not have, e.g. 2025-02-30.
+    (date(days) == format!("{:04}-{:02}-{:02}", civil_year, month, day)).then_some(days)
+}
+
+/// The text between the quote at `start` and the closing one,
+/// and the position after the latter. A quote left open runs to the end of the query.
+fn quoted(query: &str, start: usize, diagnostics: &mut Vec<QueryDiagnostic>) -> (String, usize) {
+    let content = start + 1;
+    match query[content..].find('"') {
+        Some(length) => (
+            query[content..content + length].to_string(),
+            content + length + 1,
+        ),
+        None => {
+            diagnostics.push(diagnostic(
+                query,
+                start,
+                query.len(),
+                "Unclosed quote".to_string(),
+            ));
+            (query[content..].to_string(), query.len())
+        }
+    }
+}
+
+fn next_non_space(query: &str, position: usize) -> Option<usize> {
+    query[position..]
+        .find(|c: char| !c.is_whitespace())
+        .map(|offset| position + offset)
+}
+
+/// A word ends at a space, or at a quote opening the value of a filter.
+fn word_end(query: &str, start: usize) -> usize {
+    query[start..]
+        .find(|c: char| c.is_whitespace() || c == '"')
+        .map_or(query.len(), |offset| start + offset)
+}
+
+fn diagnostic(query: &str, start: usize, end: usize, message: String) -> QueryDiagnostic {
+    let start_utf16 = query[..start].encode_utf16().count();
+    QueryDiagnostic {
+        message,
+        start: start_utf16,
+        end: start_utf16 + query[start..end].encode_utf16().count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(negated: bool, kind: TermKind) -> Term {
+        Term { negated, kind }
+    }
+
+    fn word(word: &str) -> TermKind {
+        TermKind::Word(word.to_string())
+    }
+
+    fn phrase(phrase: &str) -> TermKind {
+        TermKind::Phrase(phrase.to_string())
+    }
+
+    fn diagnostics(query: &str, context: QueryContext) -> Vec<(String, usize, usize)> {
+        parse(query, context)
+            .diagnostics
+            .into_iter()
+            .map(|d| (d.message, d.start, d.end))
+            .collect()
+    }
+
+    #[test]
+    fn words_phrases_and_filters() {
+        let parsed = parse(
+            r#"rust  "Error handling" domain:www.GitHub.com TITLE:"The Book""#,
+            QueryContext::Bookmarks,
+        );
+        assert_eq!(
+            parsed.terms,
+            [
+                term(false, word("rust")),
+                term(false, phrase("Error handling")),
+                term(false, TermKind::Filter(Filter::Domain("github.com".into()))),
+                term(false, TermKind::Filter(Filter::Title("the book".into()))),
+            ]
+        );
+        assert!(parsed.diagnostics.is_empty());
+        assert_eq!(parsed.text(), "rust");
+        assert_eq!(parsed.phrases(), ["error handling"]);
+    }
+
+    #[test]
+    fn a_dash_negates_the_term_it_is_stuck_to() {
+        let parsed = parse(r#"-beta -"release notes" - -url:old"#, QueryContext::Tabs);
+        assert_eq!(
+            parsed.terms,
+            [
+                term(true, word("beta")),
+                term(true, phrase("release notes")),
+                term(false, word("-")),
+                term(true, TermKind::Filter(Filter::Url("old".into()))),
+            ]
+        );
+        assert_eq!(parsed.text(), "-");
+        assert!(parsed.phrases().is_empty());
+    }
+
+    #[test]
+    fn an_unterminated_quote_runs_to_the_end() {
+        let parsed = parse(r#"rust "open phrase"#, QueryContext::Tabs);
+        assert_eq!(
+            parsed.terms,
+            [
+                term(false, word("rust")),
+                term(false, phrase("open phrase"))
+            ]
+        );
+        assert_eq!(
+            diagnostics(r#"rust "open phrase"#, QueryContext::Tabs),
+            [("Unclosed quote".to_string(), 5, 17)]
+        );
+
+        let parsed = parse(r#"title:"open"#, QueryContext::Tabs);
+        assert_eq!(
+            parsed.terms,
+            [term(false, TermKind::Filter(Filter::Title("open".into())))]
+        );
+        assert_eq!(parsed.diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn unknown_fields_are_words() {
+        let parsed = parse("https://example.com color:red :", QueryContext::History);
+        assert_eq!(
+            parsed.terms,
+            [
+                term(false, word("https://example.com")),
+                term(false, word("color:red")),
+                term(false, word(":")),
+            ]
+        );
+        assert!(parsed.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn invalid_filters_are_reported_and_left_out() {
+        let query = "domain: window:abc folder:dev after:2025-02-30 before:2025-13-01 rust";
+        let parsed = parse(query, QueryContext::Tabs);
+        assert_eq!(parsed.terms, [term(false, word("rust"))]);
+        assert_eq!(
+            diagnostics(query, QueryContext::Tabs),
+            [
+                ("domain: needs a value".to_string(), 0, 7),
+                ("window: expects a window id, not abc".to_string(), 8, 18),
+                ("folder: only applies to bookmarks".to_string(), 19, 29),
+                ("after: expects a date like 2025-01-01".to_string(), 30, 46),
+                ("before: expects a date like 2025-01-01".to_string(), 47, 64),
+            ]
+        );
+    }
+
+    #[test]
+    fn dates_are_days_since_the_epoch() {
+        let parsed = parse("after:1970-01-02 before:2024-02-29", QueryContext::History);
+        assert_eq!(
+            parsed.terms,
+            [
+                term(false, TermKind::Filter(Filter::After(1))),
+                term(false, TermKind::Filter(Filter::Before(19_782))),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostic_offsets_are_utf16_code_units() {
+        // `é` is 2 bytes but 1 code unit, `🦀` 4 bytes but 2 code units.
+        assert_eq!(
+            diagnostics("café 🦀 window:x", QueryContext::Tabs),
+            [("window: expects a window id, not x".to_string(), 8, 16)]
+        );
+        assert_eq!(
+            diagnostics(r#"🦀 "crabé"#, QueryContext::Tabs),
+            [("Unclosed quote".to_string(), 3, 9)]
+        );
+    }
+
+    #[test]
+    fn negated_terms_and_filters_are_checked_on_the_item() {
+        let item = serde_json::json!({
+            "url": "https://docs.github.com/en",
+            "title": "GitHub Docs",
+        });
+        let fields = ["github docs".to_string()];
+        let accepts = |query: &str| parse(query, QueryContext::Tabs).accepts(&item, &fields, 0);
+        assert!(accepts("docs domain:github.com"));
+        assert!(!accepts("domain:hub.com"));
+        assert!(!accepts("-docs"));
+        assert!(accepts(r#"-"gitlab docs" -title:gitlab"#));
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::{AppHandle, State};
+use url::Url;
+
+use crate::commands::browsers::targets;
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::models::HistoryItem;
+use crate::commands::mozeidon::read;
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::MozeidonRequest;
+use crate::common::{now_ms, Browser};
+
+const DAY_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// How search results are ranked.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RankingWeights {
+    /// Weight of how well the query matches the item.
+    pub match_weight: f64,
+    /// Weight of how frequently and recently the item's url was visited. Zero disables it.
+    pub frecency_weight: f64,
+    /// How many visits a visit typed in the address bar counts for.
+    pub typed_visits: f64,
+    /// After how many days a visit counts for half.
+    pub half_life_days: f64,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        RankingWeights {
+            match_weight: 1.0,
+            frecency_weight: 1.0,
+            typed_visits: 2.0,
+            half_life_days: 30.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct RankingState {
+    weights: RankingWeights,
+    /// The history items the frecency was computed from.
+    history: Arc<Vec<Value>>,
+    /// Frecency per url, as compared by `comparable_url`.
+    frecency: Arc<HashMap<String, f64>>,
+}
+
+/// Frecency scores of the urls of the history, the more and the later visited the higher,
+/// so that search results can be boosted by how much they are actually used.
+#[derive(Default, Clone)]
+pub struct Ranking {
+    state: Arc<Mutex<RankingState>>,
+}
+
+impl Ranking {
+    /// Computes the frecency of every url of `history`, the same url of several browsers added up.
+    /// Handing the same items again is a no-op.
+    pub fn update(&self, history: Arc<Vec<Value>>) {
+        let mut state = self.state.lock().unwrap();
+        if Arc::ptr_eq(&state.history, &history) {
+            return;
+        }
+        state.frecency = Arc::new(frecency(&history, &state.weights, now_ms()));
+        state.history = history;
+        println!("frecency of {} urls", state.frecency.len());
+    }
+
+    pub fn set_weights(&self, weights: RankingWeights) {
+        let mut state = self.state.lock().unwrap();
+        state.frecency = Arc::new(frecency(&state.history, &weights, now_ms()));
+        state.weights = weights;
+    }
+
+    /// Scores an item of `url` that matched the query with `match_score`.
+    /// `weights` overrides the configured ones, except `typed_visits` and `half_life_days`
+    /// the frecency was computed with.
+    pub fn scorer(&self, weights: Option<RankingWeights>) -> impl Fn(&str, f64) -> f64 {
+        let state = self.state.lock().unwrap();
+        let weights = weights.unwrap_or(state.weights);
+        let frecency = state.frecency.clone();
+        move |url, match_score| {
+            let frecency = if weights.frecency_weight > 0.0 {
+                frecency
+                    .get(&comparable_url(url))
+                    .copied()
+                    .unwrap_or_default()
+            } else {
+                0.0
+            };
+            weights.match_weight * match_score + weights.frecency_weight * frecency
+        }
+    }
+}
+
+/// Reads the history of `browser`, or of every configured browser, to compute frecency scores.
+/// Reading the history panel does it as well.
+#[tauri::command]
+pub async fn refresh_frecency(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    ranking: State<'_, Ranking>,
+    browser: Option<Browser>,
+) -> Result<(), ClientError> {
+    println!("refresh_frecency ({:?})", browser);
+    let request = MozeidonRequest::GetHistory {
+        max: None,
+        chunk: None,
+    };
+    let guard = registry.register_background(&request);
+    let items = read(&app, &cache, &guard, &request, targets(&app, browser)).await?;
+    ranking.update(items);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_ranking_weights(ranking: State<'_, Ranking>, weights: RankingWeights) {
+    println!("set_ranking_weights {:?}", weights);
+    ranking.set_weights(weights);
+}
+
+/// `10 * ln(1 + visits)`, each visit decaying with the age of the last one,
+/// so that a few recent visits weigh about as much as many old ones.
+fn frecency(history: &[Value], weights: &RankingWeights, now: u64) -> HashMap<String, f64> {
+    let mut visits: HashMap<String, f64> = HashMap::new();
+    for item in history {
+        let Ok(item) = HistoryItem::deserialize(item) else {
+            continue;
+        };
+        let age_days = now.saturating_sub(item.last_visit_time) as f64 / DAY_MS;
+        let decay = 0.5_f64.powf(age_days / weights.half_life_days.max(1.0));
+        let count = item.visit_count as f64 + weights.typed_visits * item.typed_count as f64;
+        *visits.entry(comparable_url(&item.url)).or_default() += count * decay;
+    }
+    visits
+        .into_iter()
+        .map(|(url, visits)| (url, 10.0 * visits.ln_1p()))
+        .collect()
+}
+
+/// `url` as compared between tabs, bookmarks and history, so that every spelling of a page matches:
+/// without its fragment and trailing slash, its scheme and host lowercased and its default port
+/// dropped. Web pages are compared without their scheme and `www.`, so that `http://example.com`,
+/// `https://www.example.com/` and `HTTP://Example.com:80/#top` are the same page.
+/// An unparsable `url` only loses its fragment and trailing slash.
+pub fn comparable_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        let page = url.split('#').next().unwrap_or_default();
+        return page.trim_end_matches('/').to_string();
+    };
+    parsed.set_fragment(None);
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return parsed.as_str().trim_end_matches('/').to_string();
+    }
+
+    let host = parsed.host_str().unwrap_or_default();
+    let mut page = host.strip_prefix("www.").unwrap_or(host).to_string();
+    if let Some(port) = parsed.port() {
+        page.push_str(&format!(":{}", port));
+    }
+    page.push_str(parsed.path().trim_end_matches('/'));
+    if let Some(query) = parsed.query() {
+        page.push_str(&format!("?{}", query));
+    }
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spellings_of_a_page_compare_equal() {
+        let page = comparable_url("http://example.com");
+        for url in [
+            "HTTP://Example.com/",
+            "https://example.com",
+            "https://www.example.com/",
+            "http://example.com:80/#top",
+            "https://EXAMPLE.com:443",
+        ] {
+            assert_eq!(comparable_url(url), page, "{}", url);
+        }
+    }
+
+    #[test]
+    fn paths_queries_and_ports_are_kept() {
+        assert_eq!(
+            comparable_url("https://www.docs.rs/serde/?search=Value#main"),
+            "docs.rs/serde?search=Value"
+        );
+        assert_eq!(comparable_url("http://localhost:8080/"), "localhost:8080");
+        assert_ne!(
+            comparable_url("https://example.com/A"),
+            comparable_url("https://example.com/a")
+        );
+        assert_ne!(
+            comparable_url("https://example.com/?a=1"),
+            comparable_url("https://example.com/?a=2")
+        );
+    }
+
+    #[test]
+    fn other_urls_keep_their_scheme() {
+        assert_eq!(
+            comparable_url("FILE:///tmp/page.html#x"),
+            "file:///tmp/page.html"
+        );
+        assert_eq!(comparable_url("about:config"), "about:config");
+        assert_eq!(comparable_url("not a url/#x"), "not a url");
+    }
+}
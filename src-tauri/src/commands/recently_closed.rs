@@ -0,0 +1,24 @@
+use tauri::{AppHandle, State};
+
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::models::RecentlyClosedItem;
+use crate::commands::mozeidon::execute_write;
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::MozeidonRequest;
+
+/// Reopens a recently closed tab, or a closed window with all of its tabs,
+/// in the browser it was read from.
+#[tauri::command]
+pub async fn restore_closed(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    item: RecentlyClosedItem,
+) -> Result<(), ClientError> {
+    let session_id = item.session_id.ok_or_else(|| {
+        ClientError::Unsupported(format!("restoring {} without a session id", item.url))
+    })?;
+    let request = MozeidonRequest::RestoreClosed { session_id };
+    execute_write(&app, &registry, &cache, &request, None, item.browser).await
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::client::ClientError;
+
+pub const DEFAULT_MAX_ITEMS: u32 = 100_000;
+pub const DEFAULT_CHUNK_SIZE: u32 = 1000;
+
+/// Which kind of items a read request yields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryContext {
+    Tabs,
+    RecentlyClosed,
+    Bookmarks,
+    History,
+}
+
+impl QueryContext {
+    /// The request reading every item of the context.
+    pub fn request(self) -> MozeidonRequest {
+        match self {
+            QueryContext::Tabs => MozeidonRequest::GetTabs,
+            QueryContext::RecentlyClosed => MozeidonRequest::GetRecentlyClosed,
+            QueryContext::Bookmarks => MozeidonRequest::GetBookmarks {
+                max: None,
+                chunk: None,
+            },
+            QueryContext::History => MozeidonRequest::GetHistory {
+                max: None,
+                chunk: None,
+            },
+        }
+    }
+}
+
+/// A tab, as addressed by the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabRef {
+    pub id: u64,
+    pub window_id: u64,
+}
+
+/// Every call the frontend can make to `mozeidon-cli`.
+/// The argv for each variant is built here, and only here.
+/// Variants the CLI has no subcommand for are only answered by the simulated browser.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum MozeidonRequest {
+    GetTabs,
+    GetRecentlyClosed,
+    SwitchTab {
+        id: u64,
+        window_id: u64,
+    },
+    CloseTab {
+        id: u64,
+        window_id: u64,
+    },
+    CloseTabs {
+        tabs: Vec<TabRef>,
+    },
+    /// Opens `url` in a new tab of the current window.
+    OpenTab {
+        url: String,
+    },
+    PinTab {
+        id: u64,
+        window_id: u64,
+        pinned: bool,
+    },
+    MuteTab {
+        id: u64,
+        window_id: u64,
+        muted: bool,
+    },
+    ReloadTab {
+        id: u64,
+        window_id: u64,
+    },
+    DuplicateTab {
+        id: u64,
+        window_id: u64,
+    },
+    DiscardTab {
+        id: u64,
+        window_id: u64,
+    },
+    /// Moves a tab to `index` of `to_window_id` (the end by default),
+    /// or into a new window.
+    MoveTab {
+        id: u64,
+        window_id: u64,
+        to_window_id: Option<u64>,
+        index: Option<u32>,
+    },
+    /// Reopens a recently closed tab or window, from its `session_id`.
+    RestoreClosed {
+        session_id: String,
+    },
+    GetBookmarks {
+        max: Option<u32>,
+        chunk: Option<u32>,
+    },
+    GetHistory {
+        max: Option<u32>,
+        chunk: Option<u32>,
+    },
+    CreateBookmark {
+        title: String,
+        url: String,
+        folder: String,
+    },
+    UpdateBookmark {
+        id: String,
+        title: String,
+        url: String,
+        folder: String,
+    },
+    DeleteBookmark {
+        id: String,
+    },
+    /// Every folder path, e.g. `/Bookmarks Toolbar/dev/`, one JSON string per line,
+    /// including the folders without bookmarks.
+    GetFolders,
+    /// An empty folder. The CLI only creates folders along with their bookmarks.
+    CreateFolder {
+        path: String,
+    },
+    /// The version of the browser extension, which only the simulated browser tells:
+    /// neither `mozeidon-cli` nor the extension answers with it.
+    GetExtensionVersion,
+}
+
+impl MozeidonRequest {
+    /// The kind of items printed by the CLI, or `None` for write requests.
+    pub fn context(&self) -> Option<QueryContext> {
+        match self {
+            MozeidonRequest::GetTabs => Some(QueryContext::Tabs),
+            MozeidonRequest::GetRecentlyClosed => Some(QueryContext::RecentlyClosed),
+            MozeidonRequest::GetBookmarks { .. } => Some(QueryContext::Bookmarks),
+            MozeidonRequest::GetHistory { .. } => Some(QueryContext::History),
+            _ => None,
+        }
+    }
+
+    /// The contexts whose items a write request changes.
+    pub fn invalidates(&self) -> &'static [QueryContext] {
+        match self {
+            MozeidonRequest::CloseTab { .. }
+            | MozeidonRequest::CloseTabs { .. }
+            | MozeidonRequest::RestoreClosed { .. } => {
+                &[QueryContext::Tabs, QueryContext::RecentlyClosed]
+            }
+            MozeidonRequest::OpenTab { .. }
+            | MozeidonRequest::PinTab { .. }
+            | MozeidonRequest::MuteTab { .. }
+            | MozeidonRequest::ReloadTab { .. }
+            | MozeidonRequest::DuplicateTab { .. }
+            | MozeidonRequest::DiscardTab { .. }
+            | MozeidonRequest::MoveTab { .. } => &[QueryContext::Tabs],
+            MozeidonRequest::CreateBookmark { .. }
+            | MozeidonRequest::UpdateBookmark { .. }
+            | MozeidonRequest::DeleteBookmark { .. }
+            | MozeidonRequest::CreateFolder { .. } => &[QueryContext::Bookmarks],
+            _ => &[],
+        }
+    }
+
+    /// Same name as the serialized `type` tag.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MozeidonRequest::GetTabs => "getTabs",
+            MozeidonRequest::GetRecentlyClosed => "getRecentlyClosed",
+            MozeidonRequest::SwitchTab { .. } => "switchTab",
+            MozeidonRequest::CloseTab { .. } => "closeTab",
+            MozeidonRequest::CloseTabs { .. } => "closeTabs",
+            MozeidonRequest::OpenTab { .. } => "openTab",
+            MozeidonRequest::PinTab { .. } => "pinTab",
+            MozeidonRequest::MuteTab { .. } => "muteTab",
+            MozeidonRequest::ReloadTab { .. } => "reloadTab",
+            MozeidonRequest::DuplicateTab { .. } => "duplicateTab",
+            MozeidonRequest::DiscardTab { .. } => "discardTab",
+            MozeidonRequest::MoveTab { .. } => "moveTab",
+            MozeidonRequest::RestoreClosed { .. } => "restoreClosed",
+            MozeidonRequest::GetBookmarks { .. } => "getBookmarks",
+            MozeidonRequest::GetHistory { .. } => "getHistory",
+            MozeidonRequest::CreateBookmark { .. } => "createBookmark",
+            MozeidonRequest::UpdateBookmark { .. } => "updateBookmark",
+            MozeidonRequest::DeleteBookmark { .. } => "deleteBookmark",
+            MozeidonRequest::GetFolders => "getFolders",
+            MozeidonRequest::CreateFolder { .. } => "createFolder",
+            MozeidonRequest::GetExtensionVersion => "getExtensionVersion",
+        }
+    }
+
+    /// The argv of the request, or `Unsupported` when `mozeidon-cli` has no subcommand for it,
+    /// rather than spawning a call bound to fail.
+    pub fn args(&self) -> Result<Vec<String>, ClientError> {
+        let (command, rest): (&[&str], Vec<String>) = match self {
+            MozeidonRequest::GetTabs => (&["tabs", "get"], vec![]),
+            MozeidonRequest::GetRecentlyClosed => (&["tabs", "get", "-c"], vec![]),
+            MozeidonRequest::SwitchTab { id, window_id } => {
+                (&["tabs", "switch"], vec![tab_id(*window_id, *id)])
+            }
+            MozeidonRequest::CloseTab { id, window_id } => {
+                (&["tabs", "close"], vec![tab_id(*window_id, *id)])
+            }
+            MozeidonRequest::CloseTabs { tabs } => (
+                &["tabs", "close"],
+                tabs.iter()
+                    .map(|tab| tab_id(tab.window_id, tab.id))
+                    .collect(),
+            ),
+            MozeidonRequest::OpenTab { url } => (&["tabs", "new"], vec![url.clone()]),
+            MozeidonRequest::PinTab { .. }
+            | MozeidonRequest::MuteTab { .. }
+            | MozeidonRequest::ReloadTab { .. }
+            | MozeidonRequest::DuplicateTab { .. }
+            | MozeidonRequest::DiscardTab { .. }
+            | MozeidonRequest::MoveTab { .. }
+            | MozeidonRequest::RestoreClosed { .. }
+            | MozeidonRequest::GetFolders
+            | MozeidonRequest::CreateFolder { .. }
+            | MozeidonRequest::GetExtensionVersion => {
+                return Err(ClientError::Unsupported(self.name().to_string()))
+            }
+            MozeidonRequest::GetBookmarks { max, chunk } => (&["bookmarks"], paging(*max, *chunk)),
+            MozeidonRequest::GetHistory { max, chunk } => (&["history"], paging(*max, *chunk)),
+            MozeidonRequest::CreateBookmark { title, url, folder } => {
+                (&["bookmark", "new"], bookmark_fields(title, url, folder))
+            }
+            MozeidonRequest::UpdateBookmark {
+                id,
+                title,
+                url,
+                folder,
+            } => (
+                &["bookmark", "update"],
+                [vec![id.clone()], bookmark_fields(title, url, folder)].concat(),
+            ),
+            MozeidonRequest::DeleteBookmark { id } => (&["bookmark", "delete"], vec![id.clone()]),
+        };
+        Ok(command.iter().map(|s| s.to_string()).chain(rest).collect())
+    }
+}
+
+/// The CLI addresses a tab as `<windowId>:<tabId>`.
+fn tab_id(window_id: u64, id: u64) -> String {
+    format!("{}:{}", window_id, id)
+}
+
+fn paging(max: Option<u32>, chunk: Option<u32>) -> Vec<String> {
+    vec![
+        "-m".to_string(),
+        max.unwrap_or(DEFAULT_MAX_ITEMS).to_string(),
+        "-c".to_string(),
+        chunk.unwrap_or(DEFAULT_CHUNK_SIZE).to_string(),
+    ]
+}
+
+fn bookmark_fields(title: &str, url: &str, folder: &str) -> Vec<String> {
+    ["-t", title, "-u", url, "-f", folder]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
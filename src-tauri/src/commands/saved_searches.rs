@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::commands::browsers::targets;
+use crate::commands::cache::ResultCache;
+use crate::commands::mozeidon::{index_items, read};
+use crate::commands::queries::QueryRegistry;
+use crate::commands::query::parse;
+use crate::commands::ranking::Ranking;
+use crate::commands::request::QueryContext;
+use crate::commands::search::{search_items, SearchIndex, SearchResults, SearchType};
+use crate::common::{get_app_setting, SETTINGS_STORE_FILENAME};
+
+/// Saved in the settings store, next to the app settings.
+const SAVED_SEARCHES_KEY: &str = "saved_searches";
+/// Emitted when saved searches are added, changed or deleted,
+/// so that the frontend registers their global shortcuts again.
+const SAVED_SEARCHES_CHANGED_EVENT: &str = "saved-searches-changed";
+/// The global shortcuts of the app settings, which a saved search cannot take.
+const PANEL_SHORTCUT_SETTINGS: &[&str] = &[
+    "global_shortcut_show_panel_tabs",
+    "global_shortcut_show_panel_bookmarks",
+    "global_shortcut_show_panel_recently_closed",
+    "global_shortcut_show_panel_history",
+];
+
+/// A query on the items of a context, kept to be run again, like a smart folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    /// Given by `save_search` when empty.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub context: QueryContext,
+    pub query: String,
+    pub search_type: SearchType,
+    /// Opens the panel on the search, e.g. `Control+Alt+J`. Empty when there is none.
+    #[serde(default)]
+    pub global_shortcut: String,
+}
+
+#[tauri::command]
+pub fn list_saved_searches(app: AppHandle) -> Result<Vec<SavedSearch>, String> {
+    println!("list_saved_searches");
+    load(&app)
+}
+
+/// Adds `search`, or replaces the saved search of the same id.
+/// Returns it with its id.
+#[tauri::command]
+pub fn save_search(app: AppHandle, mut search: SavedSearch) -> Result<SavedSearch, String> {
+    println!("save_search {:?}", search);
+    search.name = search.name.trim().to_string();
+    search.global_shortcut = search.global_shortcut.trim().to_string();
+    if search.name.is_empty() {
+        return Err("A saved search needs a name".to_string());
+    }
+    if let Some(diagnostic) = parse(&search.query, search.context).diagnostics.first() {
+        return Err(format!("Invalid query: {}", diagnostic.message));
+    }
+
+    let mut searches = load(&app)?;
+    if !search.global_shortcut.is_empty() {
+        let taken_by_search = searches.iter().find(|saved| {
+            saved.id != search.id
+                && saved
+                    .global_shortcut
+                    .eq_ignore_ascii_case(&search.global_shortcut)
+        });
+        if let Some(saved) = taken_by_search {
+            return Err(format!(
+                "{} is already the shortcut of {}",
+                search.global_shortcut, saved.name
+            ));
+        }
+        let taken_by_setting = PANEL_SHORTCUT_SETTINGS.iter().find(|setting| {
+            get_app_setting(&app, setting)
+                .and_then(|shortcut| shortcut.as_str().map(str::to_string))
+                .is_some_and(|shortcut| shortcut.eq_ignore_ascii_case(&search.global_shortcut))
+        });
+        if let Some(setting) = taken_by_setting {
+            return Err(format!(
+                "{} is already the shortcut of {}",
+                search.global_shortcut, setting
+            ));
+        }
+    }
+
+    match searches.iter_mut().find(|saved| saved.id == search.id) {
+        Some(saved) if !search.id.is_empty() => *saved = search.clone(),
+        _ => {
+            search.id = next_id(&searches);
+            searches.push(search.clone());
+        }
+    }
+    persist(&app, &searches)?;
+    Ok(search)
+}
+
+#[tauri::command]
+pub fn delete_saved_search(app: AppHandle, id: String) -> Result<(), String> {
+    println!("delete_saved_search {}", id);
+    let mut searches = load(&app)?;
+    let count = searches.len();
+    searches.retain(|saved| saved.id != id);
+    if searches.len() == count {
+        return Err(format!("No saved search {}", id));
+    }
+    persist(&app, &searches)
+}
+
+/// Reads the items of the saved search's context, from every configured browser,
+/// and searches them as the search box would.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn run_saved_search(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    index: State<'_, SearchIndex>,
+    ranking: State<'_, Ranking>,
+    id: String,
+    limit: Option<usize>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<SearchResults, String> {
+    println!("run_saved_search {}", id);
+    let search = load(&app)?
+        .into_iter()
+        .find(|saved| saved.id == id)
+        .ok_or_else(|| format!("No saved search {}", id))?;
+
+    let request = search.context.request();
+    let guard = registry.register_background(&request);
+    let items = read(&app, &cache, &guard, &request, targets(&app, None))
+        .await
+        .map_err(|e| e.to_string())?;
+    index_items(&app, search.context, &items);
+    search_items(
+        &index,
+        &ranking,
+        search.context,
+        search.query,
+        search.search_type,
+        limit,
+        None,
+        utc_offset_minutes.unwrap_or_default(),
+    )
+    .await
+}
+
+fn load(app: &AppHandle) -> Result<Vec<SavedSearch>, String> {
+    let store = app
+        .store(SETTINGS_STORE_FILENAME)
+        .map_err(|e| format!("Failed to load {}: {}", SETTINGS_STORE_FILENAME, e))?;
+    match store.get(SAVED_SEARCHES_KEY) {
+        Some(searches) => serde_json::from_value(searches)
+            .map_err(|e| format!("Invalid {}: {}", SAVED_SEARCHES_KEY, e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn persist(app: &AppHandle, searches: &[SavedSearch]) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE_FILENAME)
+        .map_err(|e| format!("Failed to load {}: {}", SETTINGS_STORE_FILENAME, e))?;
+    let searches: Value = serde_json::to_value(searches).map_err(|e| e.to_string())?;
+    store.set(SAVED_SEARCHES_KEY, searches);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save {}: {}", SETTINGS_STORE_FILENAME, e))?;
+    if let Err(e) = app.emit(SAVED_SEARCHES_CHANGED_EVENT, ()) {
+        eprintln!("Failed to emit {}: {}", SAVED_SEARCHES_CHANGED_EVENT, e);
+    }
+    Ok(())
+}
+
+/// One more than the highest numeric id.
+fn next_id(searches: &[SavedSearch]) -> String {
+    let highest = searches
+        .iter()
+        .filter_map(|saved| saved.id.parse::<u64>().ok())
+        .max()
+        .unwrap_or_default();
+    (highest + 1).to_string()
+}
@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use nucleo_matcher::pattern::{Atom, AtomKind, CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32String};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+use crate::commands::query::{parse, QueryDiagnostic};
+use crate::commands::ranking::{Ranking, RankingWeights};
+use crate::commands::request::QueryContext;
+
+const DEFAULT_LIMIT: usize = 500;
+
+/// Mirrors the `SearchType` of the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchType {
+    /// Every word of the query matches, with gaps, one of the fields.
+    Fuzzy,
+    /// The words of the query, as a whole, are part of one of the fields, ignoring case.
+    /// Matches are only ranked by frecency.
+    Exact,
+}
+
+/// An item matching a search.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub item: Value,
+    pub score: f64,
+    /// The matched ranges of each field, as `[start, end)` offsets in UTF-16 code units,
+    /// the way JavaScript indexes strings.
+    pub highlights: HashMap<&'static str, Vec<[usize; 2]>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    /// How many items matched, `hits` being the first `limit` of them.
+    pub total: usize,
+    /// The terms of the query left out of the search.
+    pub diagnostics: Vec<QueryDiagnostic>,
+}
+
+/// The searched fields of an item, in the order of `fields`.
+struct IndexedItem {
+    haystacks: Vec<Utf32String>,
+    /// Lowercased, for exact searches.
+    lowercase: Vec<String>,
+}
+
+struct IndexEntry {
+    items: Arc<Vec<Value>>,
+    fields: &'static [&'static str],
+    indexed: Vec<IndexedItem>,
+}
+
+/// The items last read by the panel, one set per context,
+/// with their searched fields ready to be matched.
+#[derive(Default, Clone)]
+pub struct SearchIndex {
+    entries: Arc<Mutex<HashMap<QueryContext, Arc<IndexEntry>>>>,
+}
+
+impl SearchIndex {
+    /// Replaces the items of `context`. Indexing the same items again is a no-op.
+    pub fn update(&self, context: QueryContext, items: Arc<Vec<Value>>) {
+        if let Some(entry) = self.entries.lock().unwrap().get(&context) {
+            if Arc::ptr_eq(&entry.items, &items) {
+                return;
+            }
+        }
+        let fields = fields(context);
+        let indexed = items
+            .iter()
+            .map(|item| {
+                let texts = fields
+                    .iter()
+                    .map(|field| item.get(field).and_then(Value::as_str).unwrap_or_default());
+                IndexedItem {
+                    haystacks: texts.clone().map(haystack).collect(),
+                    lowercase: texts.map(str::to_lowercase).collect(),
+                }
+            })
+            .collect();
+        let entry = IndexEntry {
+            items,
+            fields,
+            indexed,
+        };
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(context, Arc::new(entry));
+    }
+
+    fn entry(&self, context: QueryContext) -> Option<Arc<IndexEntry>> {
+        self.entries.lock().unwrap().get(&context).cloned()
+    }
+}
+
+/// Searches the items the panel last read for `context`, and ranks the matches
+/// by how well they match and by the frecency of their url.
+/// The query may hold filters, phrases and negated terms, see `query`;
+/// dates are days of the user's timezone, e.g. `utc_offset_minutes` of `120` for UTC+2.
+/// `weights` overrides the ranking weights for this search.
+/// An empty query matches every item.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn search(
+    index: State<'_, SearchIndex>,
+    ranking: State<'_, Ranking>,
+    context: QueryContext,
+    query: String,
+    search_type: SearchType,
+    limit: Option<usize>,
+    weights: Option<RankingWeights>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<SearchResults, String> {
+    search_items(
+        &index,
+        &ranking,
+        context,
+        query,
+        search_type,
+        limit,
+        weights,
+        utc_offset_minutes.unwrap_or_default(),
+    )
+    .await
+}
+
+/// Same as the `search` command. Matching up to every item of the history takes a while,
+/// so it runs on a blocking thread rather than on the one the command was called from.
+#[allow(clippy::too_many_arguments)]
+pub async fn search_items(
+    index: &SearchIndex,
+    ranking: &Ranking,
+    context: QueryContext,
+    query: String,
+    search_type: SearchType,
+    limit: Option<usize>,
+    weights: Option<RankingWeights>,
+    utc_offset_minutes: i32,
+) -> Result<SearchResults, String> {
+    let index = index.clone();
+    let ranking = ranking.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        search_blocking(
+            &index,
+            &ranking,
+            context,
+            &query,
+            search_type,
+            limit,
+            weights,
+            utc_offset_minutes,
+        )
+    })
+    .await
+    .map_err(|e| format!("Search failed: {}", e))?
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search_blocking(
+    index: &SearchIndex,
+    ranking: &Ranking,
+    context: QueryContext,
+    query: &str,
+    search_type: SearchType,
+    limit: Option<usize>,
+    weights: Option<RankingWeights>,
+    utc_offset_minutes: i32,
+) -> Result<SearchResults, String> {
+    let entry = index
+        .entry(context)
+        .ok_or_else(|| format!("No {:?} items to search yet", context))?;
+    let score = ranking.scorer(weights);
+    let url = |position: usize| {
+        entry.items[position]
+            .get("url")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    };
+
+    let parsed = parse(query, context);
+    let text = parsed.text();
+    let phrases = parsed.phrases();
+
+    let mut matcher = Matcher::new(Config::DEFAULT);
+    let atoms = match search_type {
+        SearchType::Fuzzy => {
+            Pattern::new(
+                &text,
+                CaseMatching::Ignore,
+                Normalization::Smart,
+                AtomKind::Fuzzy,
+            )
+            .atoms
+        }
+        SearchType::Exact => Vec::new(),
+    };
+    let exact_query = text.to_lowercase();
+    let mut matches: Vec<(usize, f64)> = entry
+        .indexed
+        .iter()
+        .enumerate()
+        .filter_map(|(position, item)| {
+            let has_phrases = phrases
+                .iter()
+                .all(|phrase| item.lowercase.iter().any(|text| text.contains(phrase)));
+            if !has_phrases
+                || !parsed.accepts(&entry.items[position], &item.lowercase, utc_offset_minutes)
+            {
+                return None;
+            }
+            let match_score = match search_type {
+                SearchType::Fuzzy => atoms
+                    .iter()
+                    .map(|atom| best_field(atom, item, &mut matcher).map(|(_, score)| score))
+                    .sum::<Option<u32>>()?,
+                SearchType::Exact => {
+                    let found = item
+                        .lowercase
+                        .iter()
+                        .any(|text| text.contains(&exact_query));
+                    found.then_some(0)?
+                }
+            };
+            Some((position, score(url(position), match_score as f64)))
+        })
+        .collect();
+    matches.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let total = matches.len();
+    let hits = matches
+        .into_iter()
+        .take(limit.unwrap_or(DEFAULT_LIMIT))
+        .map(|(position, score)| SearchHit {
+            item: entry.items[position].clone(),
+            score,
+            highlights: {
+                let mut highlights = match search_type {
+                    SearchType::Fuzzy => fuzzy_highlights(&atoms, &entry, position, &mut matcher),
+                    SearchType::Exact => exact_highlights(&exact_query, &entry, position),
+                };
+                for phrase in &phrases {
+                    for (field, ranges) in exact_highlights(phrase, &entry, position) {
+                        highlights.entry(field).or_default().extend(ranges);
+                    }
+                }
+                highlights.values_mut().for_each(merge_ranges);
+                highlights
+            },
+        })
+        .collect();
+    println!("search {:?} {:?}: {} matches", context, query, total);
+    Ok(SearchResults {
+        hits,
+        total,
+        diagnostics: parsed.diagnostics,
+    })
+}
+
+/// The field `atom` matches best, and its score.
+fn best_field(atom: &Atom, item: &IndexedItem, matcher: &mut Matcher) -> Option<(usize, u32)> {
+    item.haystacks
+        .iter()
+        .enumerate()
+        .filter_map(|(field, haystack)| {
+            atom.score(haystack.slice(..), matcher)
+                .map(|score| (field, score as u32))
+        })
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+}
+
+/// Where each atom matched, in the field it matches best.
+fn fuzzy_highlights(
+    atoms: &[Atom],
+    entry: &IndexEntry,
+    position: usize,
+    matcher: &mut Matcher,
+) -> HashMap<&'static str, Vec<[usize; 2]>> {
+    let item = &entry.indexed[position];
+    let mut indices: Vec<Vec<u32>> = vec![Vec::new(); entry.fields.len()];
+    for atom in atoms {
+        if let Some((field, _)) = best_field(atom, item, matcher) {
+            atom.indices(
+                item.haystacks[field].slice(..),
+                matcher,
+                &mut indices[field],
+            );
+        }
+    }
+
+    entry
+        .fields
+        .iter()
+        .zip(&item.haystacks)
+        .zip(indices)
+        .filter(|(_, indices)| !indices.is_empty())
+        .map(|((field, haystack), mut indices)| {
+            indices.sort_unstable();
+            indices.dedup();
+            (*field, ranges(haystack, &indices))
+        })
+        .collect()
+}
+
+/// Where `query`, lowercased, is first found in each field.
+fn exact_highlights(
+    query: &str,
+    entry: &IndexEntry,
+    position: usize,
+) -> HashMap<&'static str, Vec<[usize; 2]>> {
+    if query.is_empty() {
+        return HashMap::new();
+    }
+    entry
+        .fields
+        .iter()
+        .zip(&entry.indexed[position].lowercase)
+        .filter_map(|(field, text)| {
+            let start = text.find(query)?;
+            let original = entry.items[position]
+                .get(field)
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            Some((
+                *field,
+                vec![original_range(original, start, start + query.len())],
+            ))
+        })
+        .collect()
+}
+
+/// The `[start, end)` byte range of `original.to_lowercase()` as a range of UTF-16 code units
+/// of `original`, widened to the whole chars it covers.
+/// Some chars get longer once lowercased, e.g. `İ` into `i̇`, but lowercasing the string
+/// takes as many bytes as lowercasing each of its chars.
+fn original_range(original: &str, start: usize, end: usize) -> [usize; 2] {
+    let mut lowercase_offset = 0;
+    let mut utf16_offset = 0;
+    let mut range_start = None;
+    for c in original.chars() {
+        let next_offset = lowercase_offset + c.to_lowercase().map(char::len_utf8).sum::<usize>();
+        if range_start.is_none() && next_offset > start {
+            range_start = Some(utf16_offset);
+        }
+        utf16_offset += c.len_utf16();
+        if next_offset >= end {
+            break;
+        }
+        lowercase_offset = next_offset;
+    }
+    [range_start.unwrap_or(utf16_offset), utf16_offset]
+}
+
+/// Sorted char indices merged into `[start, end)` ranges of UTF-16 code units.
+fn ranges(haystack: &Utf32String, indices: &[u32]) -> Vec<[usize; 2]> {
+    let utf16_offset = |index: u32| match haystack {
+        Utf32String::Ascii(_) => index as usize,
+        Utf32String::Unicode(chars) => chars[..index as usize].iter().map(|c| c.len_utf16()).sum(),
+    };
+    let mut ranges: Vec<[usize; 2]> = Vec::new();
+    for &index in indices {
+        let start = utf16_offset(index);
+        let end = utf16_offset(index + 1);
+        match ranges.last_mut() {
+            Some(range) if range[1] == start => range[1] = end,
+            _ => ranges.push([start, end]),
+        }
+    }
+    ranges
+}
+
+/// Sorts `ranges` and merges those that overlap or touch.
+fn merge_ranges(ranges: &mut Vec<[usize; 2]>) {
+    ranges.sort_unstable();
+    let mut merged: Vec<[usize; 2]> = Vec::with_capacity(ranges.len());
+    for &[start, end] in ranges.iter() {
+        match merged.last_mut() {
+            Some(last) if start <= last[1] => last[1] = last[1].max(end),
+            _ => merged.push([start, end]),
+        }
+    }
+    *ranges = merged;
+}
+
+/// One haystack char per `char`, rather than per grapheme,
+/// so that match indices map back to the string.
+fn haystack(text: &str) -> Utf32String {
+    if text.is_ascii() {
+        Utf32String::Ascii(text.into())
+    } else {
+        Utf32String::Unicode(text.chars().collect())
+    }
+}
+
+/// The searched fields of each context, as the panel searched them.
+fn fields(context: QueryContext) -> &'static [&'static str] {
+    match context {
+        QueryContext::Tabs | QueryContext::RecentlyClosed => &["url", "title", "domain"],
+        QueryContext::Bookmarks => &["url", "title", "parent"],
+        QueryContext::History => &["url", "title"],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlight(original: &str, query: &str) -> [usize; 2] {
+        let start = original.to_lowercase().find(query).unwrap();
+        original_range(original, start, start + query.len())
+    }
+
+    #[test]
+    fn exact_ranges_are_offsets_of_the_original_text() {
+        assert_eq!(highlight("Hello World", "world"), [6, 11]);
+        // `İ` is 2 bytes, but 3 once lowercased.
+        assert_eq!(highlight("İstanbul guide", "guide"), [9, 14]);
+        assert_eq!(highlight("İstanbul", "i̇st"), [0, 3]);
+        // A query starting inside the lowercase form of a char covers the whole char.
+        assert_eq!(highlight("İstanbul", "\u{307}st"), [0, 3]);
+        // UTF-16 offsets, as JavaScript counts them.
+        assert_eq!(highlight("🦀 Rust", "rust"), [3, 7]);
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use tauri::async_runtime::{channel, Receiver};
+use tauri::{path::BaseDirectory, AppHandle, Manager};
+use tauri_plugin_shell::process::{CommandEvent, TerminatedPayload};
+
+use crate::commands::models::{BookmarkItem, ClosedKind, HistoryItem, RecentlyClosedItem, TabItem};
+use crate::commands::request::{MozeidonRequest, DEFAULT_CHUNK_SIZE, DEFAULT_MAX_ITEMS};
+use crate::common::{domain_of, now_ms, Browser};
+
+pub const FIXTURES_DIR_ENV_VAR: &str = "MOZEIDON_FIXTURES_DIR";
+const FIXTURES_RESOURCE_DIR: &str = "fixtures";
+
+const TABS_FIXTURE: &str = "tabs.json";
+const RECENTLY_CLOSED_FIXTURE: &str = "recently_closed.json";
+const BOOKMARKS_FIXTURE: &str = "bookmarks.json";
+const HISTORY_FIXTURE: &str = "history.json";
+
+const SIMULATED_EXTENSION_VERSION: &str = "1.0.0";
+
+#[derive(Default)]
+struct BrowserState {
+    tabs: Vec<TabItem>,
+    recently_closed: Vec<RecentlyClosedItem>,
+    bookmarks: Vec<BookmarkItem>,
+    /// Folders created empty, the others only existing through their bookmarks.
+    folders: Vec<String>,
+    history: Vec<HistoryItem>,
+    next_bookmark_id: u64,
+    next_session_id: u64,
+    next_tab_id: u64,
+}
+
+/// Fake browsers answering the same requests as `mozeidon-cli`,
+/// from JSON fixture files of items loaded on first use.
+/// Writes only change their in-memory state.
+#[derive(Default)]
+pub struct SimulatedBrowser {
+    states: Mutex<HashMap<Option<Browser>, BrowserState>>,
+}
+
+impl SimulatedBrowser {
+    /// Answers `request` with the events `mozeidon-cli` would have produced,
+    /// as `browser` if given.
+    pub fn request(
+        &self,
+        app: &AppHandle,
+        browser: Option<&Browser>,
+        request: &MozeidonRequest,
+    ) -> Receiver<CommandEvent> {
+        let mut states = self.states.lock().unwrap();
+        let state = states
+            .entry(browser.cloned())
+            .or_insert_with(|| BrowserState::load(&fixtures_dir(app, browser)));
+
+        let events = match state.handle(request) {
+            Ok(lines) => lines
+                .into_iter()
+                .map(|mut line| {
+                    line.push(b'\n');
+                    CommandEvent::Stdout(line)
+                })
+                .chain([terminated(0)])
+                .collect(),
+            Err(message) => vec![
+                CommandEvent::Stderr(format!("{}\n", message).into_bytes()),
+                terminated(1),
+            ],
+        };
+
+        let (tx, rx) = channel(events.len());
+        for event in events {
+            let _ = tx.try_send(event);
+        }
+        rx
+    }
+}
+
+/// `MOZEIDON_FIXTURES_DIR` if set, otherwise the fixtures bundled with the app.
+/// A browser gets its own subdirectory, e.g. `fixtures/chrome`, when there is one.
+fn fixtures_dir(app: &AppHandle, browser: Option<&Browser>) -> PathBuf {
+    let dir = std::env::var_os(FIXTURES_DIR_ENV_VAR)
+        .map(PathBuf::from)
+        .or_else(|| {
+            app.path()
+                .resolve(FIXTURES_RESOURCE_DIR, BaseDirectory::Resource)
+                .ok()
+        })
+        .unwrap_or_default();
+
+    match browser.map(|browser| dir.join(browser.name())) {
+        Some(browser_dir) if browser_dir.is_dir() => browser_dir,
+        _ => dir,
+    }
+}
+
+fn load_fixture<T: DeserializeOwned>(dir: &Path, filename: &str) -> Vec<T> {
+    let path = dir.join(filename);
+    let items = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()));
+    match items {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("Failed to load fixture {}: {}", path.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+/// The simulated browser knows every state of its tabs,
+/// those left out of the fixture are the defaults of a new tab.
+fn with_states(mut tab: TabItem) -> TabItem {
+    tab.active.get_or_insert(false);
+    tab.last_accessed.get_or_insert_with(now_ms);
+    tab.pinned.get_or_insert(false);
+    tab.muted.get_or_insert(false);
+    tab.discarded.get_or_insert(false);
+    tab
+}
+
+/// Splits items into `Chunk` lines, as printed by the CLI.
+fn chunk_lines<T: Serialize>(items: &[T], max: Option<u32>, chunk: Option<u32>) -> Vec<Vec<u8>> {
+    let max = max.unwrap_or(DEFAULT_MAX_ITEMS) as usize;
+    let chunk = chunk.unwrap_or(DEFAULT_CHUNK_SIZE).max(1) as usize;
+    let items = &items[..items.len().min(max)];
+    items
+        .chunks(chunk)
+        .map(|data| serde_json::to_vec(&json!({ "data": data })).unwrap_or_default())
+        .collect()
+}
+
+impl BrowserState {
+    fn load(dir: &Path) -> Self {
+        Self {
+            tabs: load_fixture(dir, TABS_FIXTURE)
+                .into_iter()
+                .map(with_states)
+                .collect(),
+            recently_closed: load_fixture(dir, RECENTLY_CLOSED_FIXTURE),
+            bookmarks: load_fixture(dir, BOOKMARKS_FIXTURE),
+            folders: Vec::new(),
+            history: load_fixture(dir, HISTORY_FIXTURE),
+            next_bookmark_id: 0,
+            next_session_id: 0,
+            next_tab_id: 0,
+        }
+    }
+
+    fn tab_position(&self, id: u64, window_id: u64) -> Result<usize, String> {
+        self.tabs
+            .iter()
+            .position(|tab| tab.id == id && tab.window_id == window_id)
+            .ok_or_else(|| format!("no tab {}:{}", window_id, id))
+    }
+
+    /// Ids of the tabs opened by the simulated browser, after the ones of the fixtures.
+    fn new_tab_id(&mut self) -> u64 {
+        self.next_tab_id += 1;
+        1_000_000 + self.next_tab_id
+    }
+
+    fn new_window_id(&self) -> u64 {
+        self.tabs.iter().map(|tab| tab.window_id).max().unwrap_or(0) + 1
+    }
+
+    /// The tab at `position`, printed as the CLI does after a tab action.
+    fn tab_lines(&self, position: usize) -> Vec<Vec<u8>> {
+        chunk_lines(&self.tabs[position..=position], None, None)
+    }
+
+    /// Closes the tab at `position`, which then shows up in the recently closed tabs.
+    fn close(&mut self, position: usize) {
+        let tab = self.tabs.remove(position);
+        self.next_session_id += 1;
+        self.recently_closed.insert(
+            0,
+            RecentlyClosedItem {
+                id: tab.id,
+                session_id: Some(format!("simulated-session-{}", self.next_session_id)),
+                kind: ClosedKind::Tab,
+                domain: tab.domain,
+                title: tab.title,
+                url: tab.url,
+                window_id: tab.window_id,
+                tab_count: 1,
+                closed_at: now_ms(),
+                browser: None,
+            },
+        );
+    }
+
+    /// Restored tabs get new ids, as in a real browser.
+    fn restore(&mut self, closed: RecentlyClosedItem) {
+        let window_id = match closed.kind {
+            ClosedKind::Tab => closed.window_id,
+            ClosedKind::Window => self.new_window_id(),
+        };
+        let id = self.new_tab_id();
+        self.tabs.push(TabItem {
+            id,
+            domain: closed.domain,
+            title: closed.title,
+            url: closed.url,
+            window_id,
+            active: Some(false),
+            last_accessed: Some(now_ms()),
+            pinned: Some(false),
+            muted: Some(false),
+            discarded: Some(false),
+            browser: None,
+        });
+    }
+
+    /// Moves the tab at `position` to `index` of `window_id`, or to the end of it.
+    /// Returns its new position.
+    fn move_tab(&mut self, position: usize, window_id: u64, index: Option<u32>) -> usize {
+        let mut tab = self.tabs.remove(position);
+        tab.window_id = window_id;
+        let in_window: Vec<usize> = (0..self.tabs.len())
+            .filter(|p| self.tabs[*p].window_id == window_id)
+            .collect();
+        let position = index
+            .and_then(|index| in_window.get(index as usize).copied())
+            .or_else(|| in_window.last().map(|p| p + 1))
+            .unwrap_or(self.tabs.len());
+        self.tabs.insert(position, tab);
+        position
+    }
+
+    fn bookmark_position(&self, id: &str) -> Result<usize, String> {
+        self.bookmarks
+            .iter()
+            .position(|bookmark| bookmark.id == id)
+            .ok_or_else(|| format!("no bookmark {}", id))
+    }
+
+    /// The folders created empty, and those of the bookmarks, with their parents, sorted.
+    fn folder_paths(&self) -> Vec<String> {
+        let mut paths = BTreeSet::new();
+        for path in self
+            .folders
+            .iter()
+            .chain(self.bookmarks.iter().map(|bookmark| &bookmark.parent))
+        {
+            let mut ancestor = String::from("/");
+            for name in path.split('/').filter(|name| !name.is_empty()) {
+                ancestor = format!("{}{}/", ancestor, name);
+                paths.insert(ancestor.clone());
+            }
+        }
+        paths.into_iter().collect()
+    }
+
+    /// Returns the stdout lines of `request`, or its stderr message on failure.
+    fn handle(&mut self, request: &MozeidonRequest) -> Result<Vec<Vec<u8>>, String> {
+        match request {
+            MozeidonRequest::GetTabs => Ok(chunk_lines(&self.tabs, None, None)),
+            MozeidonRequest::GetRecentlyClosed => {
+                Ok(chunk_lines(&self.recently_closed, None, None))
+            }
+            MozeidonRequest::GetBookmarks { max, chunk } => {
+                Ok(chunk_lines(&self.bookmarks, *max, *chunk))
+            }
+            MozeidonRequest::GetHistory { max, chunk } => {
+                Ok(chunk_lines(&self.history, *max, *chunk))
+            }
+            MozeidonRequest::SwitchTab { id, window_id } => {
+                self.tab_position(*id, *window_id)?;
+                Ok(Vec::new())
+            }
+            MozeidonRequest::CloseTab { id, window_id } => {
+                let position = self.tab_position(*id, *window_id)?;
+                self.close(position);
+                Ok(Vec::new())
+            }
+            MozeidonRequest::CloseTabs { tabs } => {
+                for tab in tabs {
+                    let position = self.tab_position(tab.id, tab.window_id)?;
+                    self.close(position);
+                }
+                Ok(Vec::new())
+            }
+            MozeidonRequest::OpenTab { url } => {
+                let window_id = self
+                    .tabs
+                    .iter()
+                    .find(|tab| tab.active == Some(true))
+                    .map(|tab| tab.window_id)
+                    .unwrap_or_else(|| self.new_window_id());
+                let id = self.new_tab_id();
+                self.tabs.push(TabItem {
+                    id,
+                    domain: domain_of(url),
+                    title: url.clone(),
+                    url: url.clone(),
+                    window_id,
+                    active: Some(false),
+                    last_accessed: Some(now_ms()),
+                    pinned: Some(false),
+                    muted: Some(false),
+                    discarded: Some(false),
+                    browser: None,
+                });
+                Ok(Vec::new())
+            }
+            MozeidonRequest::PinTab {
+                id,
+                window_id,
+                pinned,
+            } => {
+                let position = self.tab_position(*id, *window_id)?;
+                self.tabs[position].pinned = Some(*pinned);
+                Ok(self.tab_lines(position))
+            }
+            MozeidonRequest::MuteTab {
+                id,
+                window_id,
+                muted,
+            } => {
+                let position = self.tab_position(*id, *window_id)?;
+                self.tabs[position].muted = Some(*muted);
+                Ok(self.tab_lines(position))
+            }
+            MozeidonRequest::ReloadTab { id, window_id } => {
+                let position = self.tab_position(*id, *window_id)?;
+                self.tabs[position].discarded = Some(false);
+                Ok(self.tab_lines(position))
+            }
+            MozeidonRequest::DuplicateTab { id, window_id } => {
+                let position = self.tab_position(*id, *window_id)?;
+                let mut tab = self.tabs[position].clone();
+                tab.id = self.new_tab_id();
+                tab.pinned = Some(false);
+                self.tabs.insert(position + 1, tab);
+                Ok(self.tab_lines(position + 1))
+            }
+            MozeidonRequest::DiscardTab { id, window_id } => {
+                let position = self.tab_position(*id, *window_id)?;
+                self.tabs[position].discarded = Some(true);
+                Ok(self.tab_lines(position))
+            }
+            MozeidonRequest::MoveTab {
+                id,
+                window_id,
+                to_window_id,
+                index,
+            } => {
+                let position = self.tab_position(*id, *window_id)?;
+                let to_window_id = to_window_id.unwrap_or_else(|| self.new_window_id());
+                let position = self.move_tab(position, to_window_id, *index);
+                Ok(self.tab_lines(position))
+            }
+            MozeidonRequest::RestoreClosed { session_id } => {
+                let position = self
+                    .recently_closed
+                    .iter()
+                    .position(|closed| closed.session_id.as_ref() == Some(session_id))
+                    .ok_or_else(|| format!("no closed session {}", session_id))?;
+                let closed = self.recently_closed.remove(position);
+                self.restore(closed);
+                Ok(Vec::new())
+            }
+            MozeidonRequest::CreateBookmark { title, url, folder } => {
+                self.next_bookmark_id += 1;
+                self.bookmarks.push(BookmarkItem {
+                    id: format!("simulated-{}", self.next_bookmark_id),
+                    title: title.clone(),
+                    url: url.clone(),
+                    parent: folder.clone(),
+                    browser: None,
+                });
+                Ok(Vec::new())
+            }
+            MozeidonRequest::UpdateBookmark {
+                id,
+                title,
+                url,
+                folder,
+            } => {
+                let position = self.bookmark_position(id)?;
+                let bookmark = &mut self.bookmarks[position];
+                bookmark.title = title.clone();
+                bookmark.url = url.clone();
+                bookmark.parent = folder.clone();
+                Ok(Vec::new())
+            }
+            MozeidonRequest::DeleteBookmark { id } => {
+                let position = self.bookmark_position(id)?;
+                self.bookmarks.remove(position);
+                Ok(Vec::new())
+            }
+            MozeidonRequest::GetFolders => Ok(self
+                .folder_paths()
+                .iter()
+                .map(|path| serde_json::to_vec(path).unwrap_or_default())
+                .collect()),
+            MozeidonRequest::CreateFolder { path } => {
+                if self.folder_paths().contains(path) {
+                    return Err(format!("folder {} exists already", path));
+                }
+                self.folders.push(path.clone());
+                Ok(Vec::new())
+            }
+            MozeidonRequest::GetExtensionVersion => {
+                let line = json!({ "version": SIMULATED_EXTENSION_VERSION });
+                Ok(vec![serde_json::to_vec(&line).unwrap_or_default()])
+            }
+        }
+    }
+}
+
+/// The event ending a run, as if a process exited with `code`.
+fn terminated(code: i32) -> CommandEvent {
+    CommandEvent::Terminated(TerminatedPayload {
+        code: Some(code),
+        signal: None,
+    })
+}
@@ -0,0 +1,121 @@
+use tauri::{AppHandle, State};
+
+use crate::commands::cache::ResultCache;
+use crate::commands::client::ClientError;
+use crate::commands::models::TabItem;
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::MozeidonRequest;
+
+#[tauri::command]
+pub async fn pin_tab(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    tab: TabItem,
+    pinned: bool,
+) -> Result<TabItem, ClientError> {
+    let request = MozeidonRequest::PinTab {
+        id: tab.id,
+        window_id: tab.window_id,
+        pinned,
+    };
+    tab_action(&app, &registry, &cache, request, tab).await
+}
+
+#[tauri::command]
+pub async fn mute_tab(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    tab: TabItem,
+    muted: bool,
+) -> Result<TabItem, ClientError> {
+    let request = MozeidonRequest::MuteTab {
+        id: tab.id,
+        window_id: tab.window_id,
+        muted,
+    };
+    tab_action(&app, &registry, &cache, request, tab).await
+}
+
+#[tauri::command]
+pub async fn reload_tab(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    tab: TabItem,
+) -> Result<TabItem, ClientError> {
+    let request = MozeidonRequest::ReloadTab {
+        id: tab.id,
+        window_id: tab.window_id,
+    };
+    tab_action(&app, &registry, &cache, request, tab).await
+}
+
+/// Returns the new tab, opened next to `tab`.
+#[tauri::command]
+pub async fn duplicate_tab(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    tab: TabItem,
+) -> Result<TabItem, ClientError> {
+    let request = MozeidonRequest::DuplicateTab {
+        id: tab.id,
+        window_id: tab.window_id,
+    };
+    tab_action(&app, &registry, &cache, request, tab).await
+}
+
+/// Unloads `tab` from memory, it is loaded again once switched to.
+#[tauri::command]
+pub async fn discard_tab(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    tab: TabItem,
+) -> Result<TabItem, ClientError> {
+    let request = MozeidonRequest::DiscardTab {
+        id: tab.id,
+        window_id: tab.window_id,
+    };
+    tab_action(&app, &registry, &cache, request, tab).await
+}
+
+/// Moves `tab` to `index` of the window `to_window_id`, or into a new window.
+#[tauri::command]
+pub async fn move_tab(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+    cache: State<'_, ResultCache>,
+    tab: TabItem,
+    to_window_id: Option<u64>,
+    index: Option<u32>,
+) -> Result<TabItem, ClientError> {
+    let request = MozeidonRequest::MoveTab {
+        id: tab.id,
+        window_id: tab.window_id,
+        to_window_id,
+        index,
+    };
+    tab_action(&app, &registry, &cache, request, tab).await
+}
+
+/// Runs a tab action in the browser `tab` was read from,
+/// and returns the tab as printed by the CLI once updated.
+async fn tab_action(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    cache: &ResultCache,
+    request: MozeidonRequest,
+    tab: TabItem,
+) -> Result<TabItem, ClientError> {
+    println!("{} {:?} ({:?})", request.name(), request, tab.browser);
+    let (client, _guard) = registry.client(app, &request, None, tab.browser);
+    let updated = client.query::<TabItem>(&request).await?;
+    cache.invalidate_for(&request);
+
+    updated.into_iter().next().ok_or_else(|| {
+        ClientError::UnexpectedOutput(format!("no tab printed by {}", request.name()))
+    })
+}
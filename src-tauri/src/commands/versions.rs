@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_shell::ShellExt;
+
+use crate::commands::browsers::targets;
+use crate::commands::client::{ClientError, CLI_SIDECAR_NAME};
+use crate::commands::queries::QueryRegistry;
+use crate::commands::request::MozeidonRequest;
+use crate::common::Browser;
+
+/// Version requirements of each component, for this release of the app.
+const COMPATIBILITY_TABLE: &str = include_str!("../../compatibility.json");
+
+const SIDECAR_VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Deserialize)]
+struct CompatibilityTable {
+    #[serde(rename = "mozeidon-cli")]
+    cli: String,
+    #[serde(rename = "mozeidon-native-app")]
+    native_app: String,
+    #[serde(rename = "browser-extension")]
+    extension: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum Component {
+    #[serde(rename = "mozeidon-cli")]
+    Cli,
+    #[serde(rename = "mozeidon-native-app")]
+    NativeApp,
+    #[serde(rename = "browser-extension")]
+    Extension,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Verdict {
+    Ok,
+    Outdated,
+    /// The version could not be read, e.g. the browser is not connected.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentVersion {
+    pub component: Component,
+    /// Only for the extension, which is checked once per browser.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser: Option<Browser>,
+    pub version: Option<String>,
+    pub required: String,
+    pub verdict: Verdict,
+    /// Why the version is unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionReport {
+    pub components: Vec<ComponentVersion>,
+    /// Whether no component is known to be outdated.
+    /// Unknown versions do not make the report incompatible, they are only shown as such.
+    pub compatible: bool,
+}
+
+impl ComponentVersion {
+    fn check(
+        component: Component,
+        browser: Option<Browser>,
+        required: &VersionReq,
+        version: Result<Version, String>,
+    ) -> Self {
+        let (version, verdict, error) = match version {
+            Ok(version) if required.matches(&version) => (Some(version), Verdict::Ok, None),
+            Ok(version) => (Some(version), Verdict::Outdated, None),
+            Err(e) => (None, Verdict::Unknown, Some(e)),
+        };
+        Self {
+            component,
+            browser,
+            version: version.map(|v| v.to_string()),
+            required: required.to_string(),
+            verdict,
+            error,
+        }
+    }
+}
+
+/// Collects the versions of the bundled sidecars and of the extension in every configured browser,
+/// and checks them against the compatibility table shipped with the app.
+/// The version of `mozeidon-cli` is read from its `--version` output.
+/// That of the native app is unknown: it is a native messaging host reading its stdin,
+/// with no `--version` flag to rely on.
+/// That of the extension is unknown with `mozeidon-cli`, which has no way to ask for it.
+#[tauri::command]
+pub async fn check_versions(
+    app: AppHandle,
+    registry: State<'_, QueryRegistry>,
+) -> Result<VersionReport, String> {
+    let table: CompatibilityTable = serde_json::from_str(COMPATIBILITY_TABLE)
+        .map_err(|e| format!("Invalid compatibility table: {}", e))?;
+    let parse = |req: &str| {
+        VersionReq::parse(req).map_err(|e| format!("Invalid version requirement {}: {}", req, e))
+    };
+
+    let cli = sidecar_version(&app, CLI_SIDECAR_NAME).await;
+    let native_app = Err("mozeidon-native-app does not tell its version".to_string());
+    let mut components = vec![
+        ComponentVersion::check(Component::Cli, None, &parse(&table.cli)?, cli),
+        ComponentVersion::check(
+            Component::NativeApp,
+            None,
+            &parse(&table.native_app)?,
+            native_app,
+        ),
+    ];
+
+    let extension_req = parse(&table.extension)?;
+    for browser in targets(&app, None) {
+        let version = extension_version(&app, &registry, browser.clone()).await;
+        components.push(ComponentVersion::check(
+            Component::Extension,
+            browser,
+            &extension_req,
+            version,
+        ));
+    }
+
+    let compatible = !components.iter().any(|c| c.verdict == Verdict::Outdated);
+    Ok(VersionReport {
+        components,
+        compatible,
+    })
+}
+
+/// Runs `name --version`, killing it if it has not exited after `SIDECAR_VERSION_TIMEOUT`.
+async fn sidecar_version(app: &AppHandle, name: &str) -> Result<Version, String> {
+    let (mut rx, child) = app
+        .shell()
+        .sidecar(name)
+        .map_err(|e| e.to_string())?
+        .arg("--version")
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let mut text = String::new();
+    let collect = async {
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(bytes) | CommandEvent::Stderr(bytes) => {
+                    text.push_str(&String::from_utf8_lossy(&bytes));
+                    text.push('\n');
+                }
+                CommandEvent::Error(message) => return Err(message),
+                CommandEvent::Terminated(_) => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    };
+    match tokio::time::timeout(SIDECAR_VERSION_TIMEOUT, collect).await {
+        Ok(result) => result?,
+        Err(_) => {
+            let _ = child.kill();
+            return Err(format!("{} --version timed out", name));
+        }
+    }
+
+    find_version(&text).ok_or_else(|| format!("No version printed by {}", name))
+}
+
+async fn extension_version(
+    app: &AppHandle,
+    registry: &QueryRegistry,
+    browser: Option<Browser>,
+) -> Result<Version, String> {
+    let request = MozeidonRequest::GetExtensionVersion;
+    let (client, _guard) = registry.client(app, &request, None, browser);
+    let lines = client.output(&request).await.map_err(|e| match e {
+        ClientError::Unsupported(_) => {
+            "mozeidon-cli does not tell the version of the extension".to_string()
+        }
+        e => e.to_string(),
+    })?;
+    find_version(&lines.join("\n")).ok_or_else(|| "No version sent by the extension".to_string())
+}
+
+/// The first semantic version found in `text`, e.g. in `mozeidon 3.1.0` or `{"version":"v3.1.0"}`.
+fn find_version(text: &str) -> Option<Version> {
+    text.split(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')))
+        .map(|token| token.trim_start_matches('v'))
+        .find_map(|token| Version::parse(token).ok())
+}
@@ -26,6 +26,8 @@ pub async fn write_all_manifests(app: AppHandle) -> Result<Vec<ManifestWriteResu
         .map_err(|e| format!("Failed to write manifests: {}", e))
 }
 
+/// Not registered in the invoke handler yet.
+#[allow(dead_code)]
 #[tauri::command]
 pub async fn write_custom_manifest(
     relative_dir: String,
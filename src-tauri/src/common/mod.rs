@@ -1,5 +1,9 @@
 pub static MAIN_WINDOW_LABEL: &str = "main";
 
 mod native_manifests;
+mod settings;
+mod util;
 
 pub use native_manifests::*;
+pub use settings::*;
+pub use util::*;
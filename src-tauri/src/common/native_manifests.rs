@@ -5,8 +5,9 @@ use winreg::enums::*;
 use winreg::RegKey;
 
 use dirs::{config_dir, data_local_dir, home_dir};
+use serde::de::Deserializer;
 use serde::ser::Serializer;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tauri::utils::platform::current_exe;
 use tauri::{path::BaseDirectory, AppHandle, Manager};
 use thiserror::Error;
@@ -24,13 +25,15 @@ const DIR_WINDOWS_CHROME: &str = r"Google\Chrome\NativeMessagingHosts";
 const DIR_WINDOWS_EDGE: &str = r"Microsoft\Edge\NativeMessagingHosts";
 
 const PATH_PLACEHOLDER: &str = "__NATIVE_HOST_EXECUTABLE_PATH__";
-const NATIVE_HOST_NAME: &str = "mozeidon-native-app";
+pub const NATIVE_HOST_NAME: &str = "mozeidon-native-app";
 
 const MANIFEST_FIREFOX: &str = "firefox_native_manifest.json";
 const MANIFEST_CHROME: &str = "chrome_native_manifest.json";
 const MANIFEST_EDGE: &str = MANIFEST_CHROME;
 pub const MANIFEST_FILENAME: &str = "mozeidon.json";
 
+/// Only the variant of the platform being built is ever constructed.
+#[allow(dead_code, clippy::enum_variant_names)]
 #[derive(Debug, Clone, Copy, Serialize)]
 pub enum OS {
     MacOS,
@@ -59,7 +62,7 @@ impl OS {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Browser {
     Firefox,
     Chrome,
@@ -72,12 +75,17 @@ impl Serialize for Browser {
     where
         S: Serializer,
     {
-        match self {
-            Browser::Firefox => serializer.serialize_str("firefox"),
-            Browser::Chrome => serializer.serialize_str("chrome"),
-            Browser::Edge => serializer.serialize_str("edge"),
-            Browser::Custom(s) => serializer.serialize_str(s),
-        }
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Browser {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(Browser::from_str(&s))
     }
 }
 
@@ -100,6 +108,15 @@ impl Browser {
         }
     }
 
+    pub fn name(&self) -> &str {
+        match self {
+            Browser::Firefox => "firefox",
+            Browser::Chrome => "chrome",
+            Browser::Edge => "edge",
+            Browser::Custom(s) => s,
+        }
+    }
+
     pub fn all() -> &'static [Self] {
         &[Browser::Firefox, Browser::Chrome, Browser::Edge]
     }
@@ -187,19 +204,17 @@ pub fn get_resource_manifest_path(
 ) -> Result<PathBuf, NativeMessagingError> {
     app.path()
         .resolve(
-            &format!("native-manifests/{}", manifest_filename),
+            format!("native-manifests/{}", manifest_filename),
             BaseDirectory::Resource,
         )
         .map_err(|e| NativeMessagingError::ResourceResolveError(e.to_string()))
 }
 
-fn get_sidecar_path(_app: &AppHandle, sidecar_name: &str) -> Result<PathBuf, io::Error> {
+pub fn get_sidecar_path(_app: &AppHandle, sidecar_name: &str) -> Result<PathBuf, io::Error> {
     let exe_dir = current_exe()?
         .parent()
         .map(|p| p.to_path_buf())
-        .ok_or_else(|| {
-            io::Error::new(io::ErrorKind::Other, "Failed to get executable directory")
-        })?;
+        .ok_or_else(|| io::Error::other("Failed to get executable directory"))?;
     Ok(exe_dir.join(sidecar_name))
 }
 
@@ -305,7 +320,8 @@ pub fn write_manifest_file(
     // Use correct manifest template (shared or unique)
     let manifest_filename = match browser {
         Browser::Firefox => MANIFEST_FIREFOX,
-        Browser::Chrome | Browser::Edge => MANIFEST_CHROME,
+        Browser::Chrome => MANIFEST_CHROME,
+        Browser::Edge => MANIFEST_EDGE,
         _ => return Err(NativeMessagingError::UnsupportedPlatform),
     };
 
@@ -336,12 +352,12 @@ pub fn write_manifest_file(
     // Other platforms: return file path
     #[cfg(not(target_os = "windows"))]
     {
-        return Ok(ManifestWriteResult {
+        Ok(ManifestWriteResult {
             browser,
             written: true,
             path: Some(dest_path.to_string_lossy().into_owned()),
             content: Some(processed_contents),
-        });
+        })
     }
 }
 
@@ -0,0 +1,16 @@
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+/// Same store and key as `domain/settings/storage.ts` on the frontend side.
+pub const SETTINGS_STORE_FILENAME: &str = "settings.json";
+pub const APP_SETTINGS_KEY: &str = "app_settings";
+
+/// Reads one field of the app settings saved by the frontend.
+pub fn get_app_setting(app: &AppHandle, key: &str) -> Option<Value> {
+    let store = app
+        .store(SETTINGS_STORE_FILENAME)
+        .map_err(|e| eprintln!("Failed to load {}: {}", SETTINGS_STORE_FILENAME, e))
+        .ok()?;
+    store.get(APP_SETTINGS_KEY)?.get(key).cloned()
+}
@@ -6,6 +6,9 @@ mod common;
 mod setup;
 mod tray;
 
+/// Exposed for the contract tests against `mozeidon-cli` outputs.
+pub use commands::models;
+
 pub fn run() {
     let ctx = tauri::generate_context!();
     let mut app_builder = tauri::Builder::default();
@@ -22,10 +25,55 @@ pub fn run() {
     }
 
     let app = app_builder
+        .manage(commands::QueryRegistry::default())
+        .manage(commands::ResultCache::default())
+        .manage(commands::HealthMonitor::default())
+        .manage(commands::SimulatedBrowser::default())
+        .manage(commands::BulkUndo::default())
+        .manage(commands::SearchIndex::default())
+        .manage(commands::Ranking::default())
         .invoke_handler(tauri::generate_handler![
             commands::init,
             commands::mozeidon,
+            commands::mozeidon_stream,
             commands::mozeidon_write,
+            commands::restore_closed,
+            commands::pin_tab,
+            commands::mute_tab,
+            commands::reload_tab,
+            commands::duplicate_tab,
+            commands::discard_tab,
+            commands::move_tab,
+            commands::close_tabs,
+            commands::undo_close_tabs,
+            commands::get_bookmark_folders,
+            commands::create_bookmark_folder,
+            commands::rename_bookmark_folder,
+            commands::move_bookmark_folder,
+            commands::delete_bookmark_folder,
+            commands::move_bookmarks,
+            commands::export_bookmarks,
+            commands::preview_bookmark_import,
+            commands::import_bookmarks,
+            commands::get_history_stats,
+            commands::get_everything,
+            commands::resync_local_index,
+            commands::list_saved_searches,
+            commands::save_search,
+            commands::delete_saved_search,
+            commands::run_saved_search,
+            commands::search,
+            commands::refresh_frecency,
+            commands::set_ranking_weights,
+            commands::cancel_query,
+            commands::set_query_timeouts,
+            commands::set_cache_options,
+            commands::invalidate_cache,
+            commands::check_versions,
+            commands::diagnose_connection,
+            commands::start_health_monitor,
+            commands::stop_health_monitor,
+            commands::get_connection_status,
             commands::show,
             commands::hide,
             commands::write_manifest,
@@ -43,7 +91,11 @@ pub fn run() {
 
             let handle = app.handle().clone();
 
+            app.manage(commands::LocalIndex::open(&handle));
+
             tray::create(&handle)?;
+            app.state::<commands::HealthMonitor>()
+                .start_if_enabled(&handle);
             let main_window = handle.get_webview_window(MAIN_WINDOW_LABEL).unwrap();
 
             setup::default(app, main_window.clone());
@@ -0,0 +1,132 @@
+//! Checks the item models against outputs recorded from `mozeidon-cli`.
+//! After upgrading the CLI, record them again, e.g.
+//! `mozeidon-cli tabs get > tests/fixtures/cli/tabs.ndjson`,
+//! so that any change of schema shows up here.
+//!
+//! `tests/fixtures/synthetic` holds outputs written by hand, with the fields
+//! the models accept ahead of the CLI. They are not recordings: once the CLI
+//! prints such a field, record the fixture in `tests/fixtures/cli` instead.
+
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::fs;
+use std::path::PathBuf;
+
+use mozeidon_lib::models::{BookmarkItem, Chunk, HistoryItem, RecentlyClosedItem, TabItem};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+const TAB_KEYS: &[&str] = &["id", "windowId", "title", "url", "domain"];
+const RECENTLY_CLOSED_KEYS: &[&str] = &["id", "windowId", "title", "url", "domain"];
+const BOOKMARK_KEYS: &[&str] = &["id", "title", "url", "parent"];
+const HISTORY_KEYS: &[&str] = &["id", "title", "url", "tc", "vc", "t"];
+
+const RECORDED: &str = "tests/fixtures/cli";
+const SYNTHETIC: &str = "tests/fixtures/synthetic";
+
+/// Tab states the tab actions and the bulk filters use, ahead of the CLI.
+const SYNTHETIC_TAB_KEYS: &[&str] = &[
+    "id",
+    "windowId",
+    "title",
+    "url",
+    "domain",
+    "active",
+    "lastAccessed",
+    "pinned",
+    "muted",
+    "discarded",
+];
+/// Fields of the backends that know more about a closed item than the CLI.
+const SYNTHETIC_RECENTLY_CLOSED_KEYS: &[&str] = &[
+    "id",
+    "sessionId",
+    "kind",
+    "windowId",
+    "title",
+    "url",
+    "domain",
+    "tabCount",
+    "closedAt",
+];
+
+fn fixture_lines(dir: &str, name: &str) -> Vec<String> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join(dir)
+        .join(name);
+    fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Every item of the fixture has exactly `cli_keys`, decodes into `T`,
+/// and decodes again once serialized for the webview.
+fn check_fixture<T>(dir: &str, name: &str, cli_keys: &[&str])
+where
+    T: DeserializeOwned + Serialize + PartialEq + Debug,
+{
+    let expected: BTreeSet<&str> = cli_keys.iter().copied().collect();
+    let mut count = 0;
+    for (index, line) in fixture_lines(dir, name).iter().enumerate() {
+        let position = format!("{} line {}", name, index + 1);
+        let chunk: Chunk<Value> =
+            serde_json::from_str(line).unwrap_or_else(|e| panic!("{}: {}", position, e));
+
+        for item in chunk.data {
+            let keys: BTreeSet<&str> = item
+                .as_object()
+                .unwrap_or_else(|| panic!("{}: item is not an object", position))
+                .keys()
+                .map(String::as_str)
+                .collect();
+            assert_eq!(keys, expected, "{}: the CLI schema changed", position);
+
+            let decoded: T = serde_json::from_value(item.clone())
+                .unwrap_or_else(|e| panic!("{}: {}", position, e));
+            let sent = serde_json::to_value(&decoded).unwrap();
+            let received: T = serde_json::from_value(sent)
+                .unwrap_or_else(|e| panic!("{}: webview format: {}", position, e));
+            assert_eq!(received, decoded, "{}", position);
+            count += 1;
+        }
+    }
+    assert!(count > 0, "{} has no items", name);
+}
+
+#[test]
+fn tabs_match_cli_output() {
+    check_fixture::<TabItem>(RECORDED, "tabs.ndjson", TAB_KEYS);
+}
+
+#[test]
+fn recently_closed_match_cli_output() {
+    check_fixture::<RecentlyClosedItem>(RECORDED, "recently_closed.ndjson", RECENTLY_CLOSED_KEYS);
+}
+
+#[test]
+fn bookmarks_match_cli_output() {
+    check_fixture::<BookmarkItem>(RECORDED, "bookmarks.ndjson", BOOKMARK_KEYS);
+}
+
+#[test]
+fn history_matches_cli_output() {
+    check_fixture::<HistoryItem>(RECORDED, "history.ndjson", HISTORY_KEYS);
+}
+
+#[test]
+fn synthetic_tabs_decode() {
+    check_fixture::<TabItem>(SYNTHETIC, "tabs.ndjson", SYNTHETIC_TAB_KEYS);
+}
+
+#[test]
+fn synthetic_recently_closed_decode() {
+    check_fixture::<RecentlyClosedItem>(
+        SYNTHETIC,
+        "recently_closed.ndjson",
+        SYNTHETIC_RECENTLY_CLOSED_KEYS,
+    );
+}
@@ -13,7 +13,8 @@ import "./App.css"
 import { BookmarkItem } from "./domain/bookmarks/models"
 import { BmFormElement } from "./domain/bookmarks/validation"
 import { AppSettings } from "./domain/settings/models"
-import { TabItem } from "./domain/tabs/models"
+import { TabItem, tabKey } from "./domain/tabs/models"
+import { SavedSearch } from "./domain/savedSearches/models"
 import { ListContainer } from "./components/List"
 import { WebSearchListContainer } from "./components/WebSearchListContainer"
 import { BookmarksEditor, EditRefs } from "./components/BookmarksEditor"
@@ -24,26 +25,30 @@ import { WindowShortcutListener } from "./components/WindowShortcutListener"
 import { useInit } from "./hooks/useInit"
 import { SettingsProvider } from "./hooks/useSettings"
 import { useNotification } from "./hooks/useUserNotification"
-import { createBookmarkAction, updateBookmarkAction } from "./actions/actions"
+import {
+  createBookmarkAction,
+  refreshFrecencyAction,
+  updateBookmarkAction,
+} from "./actions/actions"
 import { runWithChrono } from "./utils/time"
+import { streamMozeidon } from "./utils/stream"
+import {
+  describeMozeidonError,
+  isCancelledError,
+} from "./domain/mozeidon/errors"
 import { useListNavigation } from "./utils/itemsInViewPort"
 import {
   handleSearch,
+  QueryDiagnostic,
   toggleSearchType,
   SearchType,
 } from "./utils/searchHandler"
-import {
-  Context,
-  GET_BOOKMARKS_COMMAND,
-  GET_HISTORY_COMMAND,
-  GET_RECENTLY_CLOSED_COMMAND,
-  GET_TABS_COMMAND,
-  RowDisplay,
-} from "./utils/constants"
+import { Context, RowDisplay } from "./utils/constants"
 import { HistoryItem } from "./domain/history/models"
+import { RecentlyClosedItem } from "./domain/recentlyClosed/models"
 import { keyDownHandler } from "./utils/keyDownHandler"
 
-type Items = BookmarkItem[] | TabItem[] | HistoryItem[]
+type Items = BookmarkItem[] | TabItem[] | HistoryItem[] | RecentlyClosedItem[]
 
 const resetWindowMultiLine = () => {
   window.f = 0
@@ -67,11 +72,15 @@ function App() {
   const [rowDisplay, setRowDisplay] = useState<RowDisplay>(RowDisplay.OneLine)
   const [searchTerms, setSearchTerms] = useState("")
   const [searchType, setSearchType] = useState<SearchType>(SearchType.Exact)
+  const [queryDiagnostics, setQueryDiagnostics] = useState<QueryDiagnostic[]>(
+    []
+  )
   const [debouncedSearch] = useDebounce(searchTerms, 200)
   const [selectedListIndex, setSelectedListIndex] = useState(0)
   const [selectedWebSearchListIndex, setSelectedWebSearchListIndex] =
     useState(0)
   const [showEditionTab, setShowEditionTab] = useState(false)
+  const [undoToken, setUndoToken] = useState<string>()
   const editRefs = useRef<EditRefs>(null)
   const listRef = useRef<List>(null)
 
@@ -126,6 +135,13 @@ function App() {
     })
   }, [])
 
+  /* Rank search results by frecency before the history panel is ever opened */
+  useEffect(() => {
+    refreshFrecencyAction().catch((e) =>
+      console.warn("Failed to compute frecency", e)
+    )
+  }, [])
+
   useListNavigation(
     listRef,
     selectedListIndex,
@@ -144,14 +160,29 @@ function App() {
     setContext(Context.Tabs)
     await invoke("show")
     /* fetch items */
-    const { res, duration } = await runWithChrono(() =>
-      invoke("mozeidon", { context: Context.Tabs, args: GET_TABS_COMMAND })
-    )
+    try {
+      const { duration } = await runWithChrono(fetchTabs)
+      notify(`took ${duration} ms !`)
+    } catch (error) {
+      /* a newer query took over, leave the panel to it */
+      if (isCancelledError(error)) return
+      notify(describeMozeidonError(error))
+    }
+    setIsLoading(false)
+  }
+
+  async function fetchTabs() {
+    const res = await invoke("mozeidon", { request: { type: "getTabs" } })
     const items: TabItem[] = JSON.parse(res as string)
     setHItems(items)
     setFuzzyItems(items)
-    notify(`took ${duration} ms !`)
-    setIsLoading(false)
+  }
+
+  /* After tabs were reopened, since they get new ids */
+  async function reloadTabs() {
+    setClosedItems([])
+    setSearchTerms("")
+    await fetchTabs()
   }
 
   const historyShortcutHandler = async () => {
@@ -159,17 +190,17 @@ function App() {
     /* show ui */
     setContext(Context.History)
     await invoke("show")
-    /* fetch items */
-    const { res, duration } = await runWithChrono(() =>
-      invoke("mozeidon", {
-        context: Context.History,
-        args: GET_HISTORY_COMMAND,
-      })
-    )
-    const items: HistoryItem[] = JSON.parse(res as string)
-    setHItems(items)
-    setFuzzyItems(items)
-    notify(`took ${duration} ms !`)
+    /* fetch items, rendering each chunk as soon as it arrives */
+    try {
+      const { duration } = await runWithChrono(() =>
+        streamMozeidon<HistoryItem>({ type: "getHistory" }, appendItems)
+      )
+      notify(`took ${duration} ms !`)
+    } catch (error) {
+      /* a newer query took over, leave the panel to it */
+      if (isCancelledError(error)) return
+      notify(describeMozeidonError(error))
+    }
     setIsLoading(false)
   }
 
@@ -179,16 +210,19 @@ function App() {
     setContext(Context.RecentlyClosed)
     await invoke("show")
     /* fetch items */
-    const { res, duration } = await runWithChrono(() =>
-      invoke("mozeidon", {
-        context: Context.Tabs,
-        args: GET_RECENTLY_CLOSED_COMMAND,
-      })
-    )
-    const items: TabItem[] = JSON.parse(res as string)
-    setHItems(items)
-    setFuzzyItems(items)
-    notify(`took ${duration} ms !`)
+    try {
+      const { res, duration } = await runWithChrono(() =>
+        invoke("mozeidon", { request: { type: "getRecentlyClosed" } })
+      )
+      const items: RecentlyClosedItem[] = JSON.parse(res as string)
+      setHItems(items)
+      setFuzzyItems(items)
+      notify(`took ${duration} ms !`)
+    } catch (error) {
+      /* a newer query took over, leave the panel to it */
+      if (isCancelledError(error)) return
+      notify(describeMozeidonError(error))
+    }
     setIsLoading(false)
   }
 
@@ -197,18 +231,54 @@ function App() {
     /* show ui */
     setContext(Context.Bookmarks)
     await invoke("show")
-    /* fetch items */
-    const { res, duration } = await runWithChrono(() =>
-      invoke("mozeidon", {
-        context: Context.Bookmarks,
-        args: GET_BOOKMARKS_COMMAND,
-      })
-    )
-    const items: BookmarkItem[] = JSON.parse(res as string)
-    setHItems(items)
-    setFuzzyItems(items)
+    /* fetch items, rendering each chunk as soon as it arrives */
+    try {
+      const { duration } = await runWithChrono(() =>
+        streamMozeidon<BookmarkItem>({ type: "getBookmarks" }, appendItems)
+      )
+      notify(`took ${duration} ms !`)
+    } catch (error) {
+      /* a newer query took over, leave the panel to it */
+      if (isCancelledError(error)) return
+      notify(describeMozeidonError(error))
+    }
+    setIsLoading(false)
+  }
+
+  /* Opens the panel of the saved search's context, filtered by its query */
+  const savedSearchHandler = async (search: SavedSearch) => {
+    switch (search.context) {
+      case "tabs":
+        await tabsShortcutHandler()
+        break
+      case "recentlyClosed":
+        await recentlyClosedShortcutHandler()
+        break
+      case "bookmarks":
+        await bookmarksShortcutHandler()
+        break
+      case "history":
+        await historyShortcutHandler()
+        break
+    }
+    setSearchType(search.searchType)
+    setSearchTerms(search.query)
+  }
+
+  /* Puts what a tab action returned in place of the tab, without a new query */
+  function replaceTab(tab: TabItem, tabs: TabItem[]) {
+    const replace = (items: Items) =>
+      (items as TabItem[]).flatMap((item) =>
+        tabKey(item) === tabKey(tab) ? tabs : [item]
+      )
+    setHItems(replace)
+    setFuzzyItems(replace)
+  }
+
+  function appendItems<T extends Items[number]>(items: T[]) {
+    setHItems((previous) => [...previous, ...items] as Items)
+    setFuzzyItems((previous) => [...previous, ...items] as Items)
     setIsLoading(false)
-    notify(`took ${duration} ms !`)
   }
 
   /* Handle KeyDown */
@@ -229,15 +299,38 @@ function App() {
       setSelectedListIndex,
       setSelectedWebSearchListIndex,
       setClosedItems,
+      replaceTab,
+      undoToken,
+      setUndoToken,
+      reloadTabs,
       restoreDefaults,
     })
 
   /* Trigger search with debounce */
   useEffect(() => {
+    /* An answer to an older search must not replace a newer one */
+    let outdated = false
+    const setSearchResults: typeof setFuzzyItems = (items) => {
+      if (!outdated) setFuzzyItems(items)
+    }
+    const setDiagnostics = (diagnostics: QueryDiagnostic[]) => {
+      if (!outdated) setQueryDiagnostics(diagnostics)
+    }
     if (debouncedSearch !== "") {
-      handleSearch(setFuzzyItems, searchType, searchTerms, context, hItems)
+      handleSearch(
+        setSearchResults,
+        searchType,
+        searchTerms,
+        context,
+        hItems,
+        setDiagnostics
+      )
     } else {
       setFuzzyItems(hItems)
+      setQueryDiagnostics([])
+    }
+    return () => {
+      outdated = true
     }
   }, [debouncedSearch, searchType])
 
@@ -247,18 +340,26 @@ function App() {
     e.preventDefault()
     const { title, url, folderPath } = e.currentTarget.elements
     if (context === Context.Tabs) {
-      await createBookmarkAction(title.value, url.value, folderPath.value)
+      const tab = (fuzzyItems as TabItem[])[selectedListIndex]
+      await createBookmarkAction(
+        title.value,
+        url.value,
+        folderPath.value,
+        tab?.browser
+      )
       setShowEditionTab(false)
       notify("Bookmark created !")
     }
 
     if (context === Context.Bookmarks) {
-      const bookmarkId = (fuzzyItems as BookmarkItem[])[selectedListIndex].id
+      const bookmark = (fuzzyItems as BookmarkItem[])[selectedListIndex]
+      const bookmarkId = bookmark.id
       await updateBookmarkAction(
         bookmarkId,
         title.value,
         url.value,
-        folderPath.value
+        folderPath.value,
+        bookmark.browser
       )
       setShowEditionTab(false)
       const newItems = fuzzyItems.map((item) => {
@@ -304,6 +405,7 @@ function App() {
         global_shortcut_show_panel_recently_closed:
           recentlyClosedShortcutHandler,
       }}
+      savedSearchHandler={savedSearchHandler}
     >
       <WindowShortcutListener
         closeWindowCallback={async () => {
@@ -350,6 +452,7 @@ function App() {
                   <SearchInput
                     ref={searchInputRef}
                     value={searchTerms}
+                    diagnostics={queryDiagnostics}
                     onChange={(e) => {
                       restoreWindow()
                       setSearchTerms(e.currentTarget.value)
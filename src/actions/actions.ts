@@ -1,17 +1,25 @@
 import { Command } from "@tauri-apps/plugin-shell"
 import { writeText } from "@tauri-apps/plugin-clipboard-manager"
 import { invoke } from "@tauri-apps/api/core"
+import { MozeidonRequest } from "../domain/mozeidon/requests"
+import { BulkSummary, TabFilter, TabItem } from "../domain/tabs/models"
+import { RecentlyClosedItem } from "../domain/recentlyClosed/models"
+import { isMozeidonError } from "../domain/mozeidon/errors"
 import {
-  CLOSE_TAB_COMMAND,
-  Context,
-  CREATE_BOOKMARK_COMMAND,
-  DELETE_BOOKMARK_COMMAND,
-  SWITCH_TAB_COMMAND,
-  UPDATE_BOOKMARK_COMMAND,
-} from "../utils/constants"
+  BookmarkTree,
+  ImportPreview,
+  ImportSummary,
+} from "../domain/bookmarks/models"
+import {
+  HistoryStats,
+  HistoryStatsOptions,
+} from "../domain/history/models"
 import { fetchCustomBrowserManifests } from "../domain/settings/storage"
 import { BrowserManifest } from "../domain/settings/models"
 import { getPlatform } from "../utils/getPlatform"
+import { RankingWeights, SearchResults } from "../utils/searchHandler"
+import { EverythingItem } from "../domain/everything/models"
+import { SavedSearch } from "../domain/savedSearches/models"
 export async function openURLAction(url: string, browser: string) {
   switch (getPlatform()) {
     case "macos":
@@ -27,10 +35,10 @@ export async function copyUrlToClipboard(url: string) {
   await writeText(url)
 }
 
-export async function switchTabAction(itemId: string, browser: string) {
+export async function switchTabAction(tab: TabItem, browser: string) {
   await invoke("mozeidon", {
-    context: Context.Tabs,
-    args: SWITCH_TAB_COMMAND + " " + itemId,
+    request: { type: "switchTab", id: tab.id, windowId: tab.windowId },
+    browser: tab.browser,
   })
   await switchToBrowserWindow(browser)
 }
@@ -44,57 +52,298 @@ export async function switchToBrowserWindow(browser: string) {
   }
 }
 
-export async function closeTabAction(itemId: string) {
+export async function closeTabAction(tab: TabItem) {
   await invoke("mozeidon", {
-    context: Context.Tabs,
-    args: CLOSE_TAB_COMMAND + " " + itemId,
+    request: { type: "closeTab", id: tab.id, windowId: tab.windowId },
+    browser: tab.browser,
   })
 }
 
-export async function deleteBookmarkAction(bookmarkId: string) {
-  await invoke("mozeidon_write", {
-    args: [...DELETE_BOOKMARK_COMMAND.split(" "), bookmarkId],
-  })
+/*
+ * Closes every tab matching the filter, pinned tabs excepted,
+ * in the given browser or in every configured one.
+ */
+export async function closeTabsAction(
+  filter: TabFilter,
+  browser?: string
+): Promise<BulkSummary> {
+  return await invoke("close_tabs", { filter, browser })
+}
+
+/*
+ * Reopens the tabs of a bulk close, returns how many were reopened.
+ */
+export async function undoCloseTabsAction(undoToken: string): Promise<number> {
+  return await invoke("undo_close_tabs", { undoToken })
+}
+
+/*
+ * Reopens the closed tab, or the closed window with all of its tabs,
+ * in the browser it was closed in.
+ */
+/*
+ * Restores the closed tab or window when the backend knows its session,
+ * otherwise opens its url again : mozeidon-cli cannot restore a session.
+ */
+export async function restoreClosedAction(
+  item: RecentlyClosedItem,
+  browser: string
+) {
+  if (!item.sessionId) {
+    await openURLAction(item.url, browser)
+    return
+  }
+  try {
+    await invoke("restore_closed", { item })
+  } catch (error) {
+    if (!isMozeidonError(error) || error.kind !== "unsupported") throw error
+    await openURLAction(item.url, browser)
+    return
+  }
+  await switchToBrowserWindow(browser)
+}
+
+/*
+ * Tab actions return the tab once updated,
+ * or the new tab for duplicateTabAction.
+ */
+export async function pinTabAction(tab: TabItem): Promise<TabItem> {
+  return await invoke("pin_tab", { tab, pinned: !tab.pinned })
+}
+
+export async function muteTabAction(tab: TabItem): Promise<TabItem> {
+  return await invoke("mute_tab", { tab, muted: !tab.muted })
+}
+
+export async function reloadTabAction(tab: TabItem): Promise<TabItem> {
+  return await invoke("reload_tab", { tab })
+}
+
+export async function duplicateTabAction(tab: TabItem): Promise<TabItem> {
+  return await invoke("duplicate_tab", { tab })
+}
+
+export async function discardTabAction(tab: TabItem): Promise<TabItem> {
+  return await invoke("discard_tab", { tab })
+}
+
+export async function moveTabToNewWindowAction(
+  tab: TabItem
+): Promise<TabItem> {
+  return await invoke("move_tab", { tab })
+}
+
+export async function deleteBookmarkAction(
+  bookmarkId: string,
+  browser?: string
+) {
+  await mozeidonWrite({ type: "deleteBookmark", id: bookmarkId }, browser)
 }
 
 export async function createBookmarkAction(
   title: string,
   url: string,
-  parent: string
+  parent: string,
+  browser?: string
 ) {
-  await invoke("mozeidon_write", {
-    args: [
-      ...CREATE_BOOKMARK_COMMAND.split(" "),
-      "-t",
-      title,
-      "-u",
-      url,
-      "-f",
-      parent,
-    ],
-  })
+  await mozeidonWrite(
+    { type: "createBookmark", title, url, folder: parent },
+    browser
+  )
 }
 
 export async function updateBookmarkAction(
   bookmarkId: string,
   title: string,
   url: string,
-  parent: string
+  parent: string,
+  browser?: string
 ) {
-  await invoke("mozeidon_write", {
-    args: [
-      ...UPDATE_BOOKMARK_COMMAND.split(" "),
-      bookmarkId,
-      "-t",
+  await mozeidonWrite(
+    {
+      type: "updateBookmark",
+      id: bookmarkId,
       title,
-      "-u",
       url,
-      "-f",
-      parent,
-    ],
+      folder: parent,
+    },
+    browser
+  )
+}
+
+/*
+ * One folder tree per browser. With mozeidon-cli, which cannot list folders,
+ * it holds the folders of the bookmarks only.
+ */
+export async function getBookmarkFoldersAction(
+  browser?: string
+): Promise<BookmarkTree[]> {
+  return await invoke("get_bookmark_folders", { browser })
+}
+
+/*
+ * Not supported by mozeidon-cli, which creates folders along with their bookmarks.
+ */
+export async function createBookmarkFolderAction(
+  path: string,
+  browser?: string
+) {
+  await invoke("create_bookmark_folder", { path, browser })
+}
+
+export async function renameBookmarkFolderAction(
+  path: string,
+  name: string,
+  browser?: string
+) {
+  await invoke("rename_bookmark_folder", { path, name, browser })
+}
+
+export async function moveBookmarkFolderAction(
+  path: string,
+  toParent: string,
+  browser?: string
+) {
+  await invoke("move_bookmark_folder", { path, toParent, browser })
+}
+
+/*
+ * A folder that is not empty is only deleted if recursive,
+ * and an empty one cannot be deleted with mozeidon-cli.
+ */
+export async function deleteBookmarkFolderAction(
+  path: string,
+  recursive: boolean,
+  browser?: string
+) {
+  await invoke("delete_bookmark_folder", { path, recursive, browser })
+}
+
+export async function moveBookmarksAction(
+  ids: string[],
+  folder: string,
+  browser?: string
+) {
+  await invoke("move_bookmarks", { ids, folder, browser })
+}
+
+/*
+ * Writes the bookmarks to a Netscape bookmarks.html file,
+ * returns how many were written.
+ */
+export async function exportBookmarksAction(
+  path: string,
+  browser?: string
+): Promise<number> {
+  return await invoke("export_bookmarks", { path, browser })
+}
+
+/*
+ * What importing a Netscape bookmarks.html file would create,
+ * and which of its bookmarks already exist.
+ */
+export async function previewBookmarkImportAction(
+  path: string,
+  intoFolder?: string,
+  browser?: string
+): Promise<ImportPreview> {
+  return await invoke("preview_bookmark_import", { path, intoFolder, browser })
+}
+
+export async function importBookmarksAction(
+  path: string,
+  intoFolder?: string,
+  browser?: string
+): Promise<ImportSummary> {
+  return await invoke("import_bookmarks", { path, intoFolder, browser })
+}
+
+/*
+ * Days and hours default to the local timezone.
+ */
+export async function getHistoryStatsAction(
+  options: HistoryStatsOptions = {},
+  browser?: string
+): Promise<HistoryStats> {
+  return await invoke("get_history_stats", {
+    options: {
+      utcOffsetMinutes: -new Date().getTimezoneOffset(),
+      ...options,
+    },
+    browser,
   })
 }
 
+/*
+ * The tabs, bookmarks and history merged into one list ranked by frecency.
+ */
+export async function getEverythingAction(
+  browser?: string,
+  weights?: RankingWeights
+): Promise<EverythingItem[]> {
+  return await invoke("get_everything", { browser, weights })
+}
+
+/*
+ * Reads the history to rank search results by how often
+ * and how lately their urls were visited.
+ */
+export async function refreshFrecencyAction(browser?: string) {
+  await invoke("refresh_frecency", { browser })
+}
+
+export async function setRankingWeightsAction(weights: RankingWeights) {
+  await invoke("set_ranking_weights", { weights })
+}
+
+/*
+ * Reads the bookmarks and the history again in full into the local index,
+ * which otherwise only syncs the pages visited since its newest one.
+ */
+export async function resyncLocalIndexAction(browser?: string) {
+  await invoke("resync_local_index", { browser })
+}
+
+export async function listSavedSearchesAction(): Promise<SavedSearch[]> {
+  return await invoke("list_saved_searches")
+}
+
+/*
+ * Adds the search, or replaces the one of the same id.
+ * Rejects an invalid query, or a global shortcut already taken.
+ */
+export async function saveSearchAction(
+  search: SavedSearch
+): Promise<SavedSearch> {
+  return await invoke("save_search", { search })
+}
+
+export async function deleteSavedSearchAction(id: string) {
+  await invoke("delete_saved_search", { id })
+}
+
+/*
+ * The items of every configured browser matching the saved search.
+ */
+export async function runSavedSearchAction<T>(
+  id: string,
+  limit?: number
+): Promise<SearchResults<T>> {
+  return await invoke("run_saved_search", {
+    id,
+    limit,
+    utcOffsetMinutes: -new Date().getTimezoneOffset(),
+  })
+}
+
+/*
+ * Writes are routed to the browser the item was read from,
+ * or to the default one when it is not tagged.
+ */
+async function mozeidonWrite(request: MozeidonRequest, browser?: string) {
+  await invoke("mozeidon_write", { request, browser })
+}
+
 export async function getBrowserManifests(): Promise<BrowserManifest[]> {
   let customManifests = await fetchCustomBrowserManifests()
   let result: BrowserManifest[] = await invoke(
@@ -49,6 +49,25 @@ export function AppSettingsJsonEditor({
           <b>theme : </b>light | dark | system
         </span>
         <br />
+        <span>
+          <b>backend : </b>sidecar | simulated. How the app talks to your
+          browser : one mozeidon-cli process per query, or no browser at all
+          (demo items).
+        </span>
+        <br />
+        <span>
+          <b>browsers : </b>e.g ["firefox", "chrome"]. Browsers queried at the
+          same time, whose items are merged, with the simulated backend only :
+          mozeidon-cli always queries the one browser the native app is
+          connected to.
+        </span>
+        <br />
+        <span>
+          <b>health_monitor : </b>true | false. Check the connection to your
+          browsers in the background, and show it in the tray icon tooltip.
+          Applies on next start.
+        </span>
+        <br />
         <span>
           <b>web_browser : </b>The web browser you are redirected into, when
           opening items.
@@ -114,6 +133,61 @@ export function AppSettingsJsonEditor({
           <b>shortcut_edit_bookmark : </b>A shortcut to edit or create a
           bookmark.
         </span>
+        <br />
+        <span>
+          <b>shortcut_pin_tab : </b>A shortcut to pin or unpin the selected
+          tab.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_mute_tab : </b>A shortcut to mute or unmute the selected
+          tab.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_reload_tab : </b>A shortcut to reload the selected tab.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_duplicate_tab : </b>A shortcut to duplicate the selected
+          tab.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_discard_tab : </b>A shortcut to unload the selected tab
+          from memory, until you switch to it again.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_move_tab_to_new_window : </b>A shortcut to move the
+          selected tab into a new window.
+        </span>
+        <br />
+        <span>
+          The six tab shortcuts above only work with the simulated backend,
+          mozeidon-cli cannot pin, mute, reload, duplicate, unload or move a
+          tab yet.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_close_matching_tabs : </b>A shortcut to close every tab
+          matching the search, pinned tabs excepted.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_close_other_tabs_in_window : </b>A shortcut to close
+          every tab of the selected tab window, except the active one.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_close_tabs_of_domain : </b>A shortcut to close every tab
+          of the selected tab domain.
+        </span>
+        <br />
+        <span>
+          <b>shortcut_undo_close_tabs : </b>A shortcut to reopen the tabs closed
+          by the last of the three shortcuts above, in the current window.
+        </span>
       </div>
       <JsonEditor
         theme={[
@@ -1,12 +1,18 @@
 import {
   forwardRef,
   RefObject,
+  useEffect,
   useImperativeHandle,
   useRef,
   useState,
 } from "react"
 import { handleFocus } from "../utils/strings"
-import { BookmarkItem } from "../domain/bookmarks/models"
+import {
+  BookmarkFolder,
+  BookmarkItem,
+  flattenFolders,
+} from "../domain/bookmarks/models"
+import { getBookmarkFoldersAction } from "../actions/actions"
 import {
   BmFormElement,
   isValidBookmark,
@@ -45,6 +51,19 @@ export const BookmarksEditor = forwardRef<EditRefs, EditFormProps>(
     const [validationErrors, setValidationErrors] = useState<
       ValidationError[] | null
     >(null)
+    const [folders, setFolders] = useState<BookmarkFolder[]>([])
+
+    useEffect(() => {
+      getBookmarkFoldersAction(selectedItem.browser)
+        .then((trees) =>
+          setFolders(
+            trees
+              .flatMap((tree) => flattenFolders(tree.root))
+              .filter((folder) => folder.path !== "/")
+          )
+        )
+        .catch((e) => console.error("Failed to load bookmark folders", e))
+    }, [selectedItem.browser])
 
     return !validationErrors ? (
       <form
@@ -103,6 +122,25 @@ export const BookmarksEditor = forwardRef<EditRefs, EditFormProps>(
             id="folderPath"
             ref={editParentRef}
           />
+          {folders.length > 0 && (
+            <div className="formDocInfo folderSuggestions">
+              {folders.map((folder) => (
+                <button
+                  key={folder.path}
+                  type="button"
+                  className="folderSuggestion"
+                  tabIndex={-1}
+                  onClick={() => {
+                    if (editParentRef.current) {
+                      editParentRef.current.value = folder.path
+                    }
+                  }}
+                >
+                  {folder.path} ({folder.totalCount})
+                </button>
+              ))}
+            </div>
+          )}
           <div className="actionContainer">
             <button className="actionButton" type="submit">
               Save &#x2713;
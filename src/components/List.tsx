@@ -10,12 +10,17 @@ import {
 import { FixedSizeList as List } from "react-window"
 import { getRows } from "./Row"
 import { HistoryItem } from "../domain/history/models"
+import { RecentlyClosedItem } from "../domain/recentlyClosed/models"
 
 type ListContainerProps = {
   rowDisplay: RowDisplay
   closedItems: string[]
   selectedListIndex: number
-  fuzzyItems: BookmarkItem[] | TabItem[] | HistoryItem[]
+  fuzzyItems:
+    | BookmarkItem[]
+    | TabItem[]
+    | HistoryItem[]
+    | RecentlyClosedItem[]
   setSelectedListIndex: React.Dispatch<React.SetStateAction<number>>
   setShowEditionTab: React.Dispatch<React.SetStateAction<boolean>>
   setClosedItems: React.Dispatch<React.SetStateAction<string[]>>
@@ -1,6 +1,6 @@
 import { useEffect, useRef, useState } from "react"
 import { BookmarkItem } from "../domain/bookmarks/models"
-import { TabItem } from "../domain/tabs/models"
+import { TabItem, tabKey } from "../domain/tabs/models"
 import { Context, FILE_PREFIX_URL, RowDisplay } from "../utils/constants"
 import { countUpperCaseOrNumberChars } from "../utils/strings"
 import { TextSelector } from "./TextSelector"
@@ -15,13 +15,19 @@ import {
   copyUrlToClipboard,
   deleteBookmarkAction,
   openURLAction,
+  restoreClosedAction,
   switchTabAction,
 } from "../actions/actions"
 import { useNotification } from "../hooks/useUserNotification"
 import { HistoryItem } from "../domain/history/models"
+import {
+  RecentlyClosedItem,
+  closedKey,
+} from "../domain/recentlyClosed/models"
 import { invoke } from "@tauri-apps/api/core"
+import { describeMozeidonError } from "../domain/mozeidon/errors"
 
-type Item = TabItem | BookmarkItem | HistoryItem
+type Item = TabItem | BookmarkItem | HistoryItem | RecentlyClosedItem
 export interface RowProps<T> {
   index: number
   style?: React.CSSProperties
@@ -44,8 +50,13 @@ export function getRows(context: Context) {
     case Context.None:
     case Context.Settings:
     case Context.Tabs:
-    case Context.RecentlyClosed:
       return TabRow as ({ index, style, data }: RowProps<Item>) => JSX.Element
+    case Context.RecentlyClosed:
+      return RecentlyClosedRow as ({
+        index,
+        style,
+        data,
+      }: RowProps<Item>) => JSX.Element
     case Context.Bookmarks:
       return BookmarkRow as ({
         index,
@@ -126,8 +137,10 @@ export const HistoryRow = ({ index, style, data }: RowProps<HistoryItem>) => {
   }
   const url = data.items.map(({ url }) => url)[index]
   const title = data.items.map(({ title }) => title)[index]
-  const visitCount = data.items.map(({ vc }) => vc)[index]
-  const lastVisit = new Date(data.items.map(({ t }) => t)[index])
+  const visitCount = data.items.map(({ visitCount }) => visitCount)[index]
+  const lastVisit = new Date(
+    data.items.map(({ lastVisitTime }) => lastVisitTime)[index]
+  )
   const lastVisitLocalDate = lastVisit.toLocaleDateString(
     settings.date_locale || undefined
   )
@@ -328,7 +341,7 @@ export const BookmarkRow = ({ index, style, data }: RowProps<BookmarkItem>) => {
   const handleOnCloseButtonClick = async (e: React.MouseEvent) => {
     e.preventDefault()
     if (isRowClosedItem) return
-    await deleteBookmarkAction(item.id)
+    await deleteBookmarkAction(item.id, item.browser)
     data.setClosedItems([...data.closedItems, item.id])
     notify("Bookmark deleted !")
     document.getElementById("searchInput")?.focus()
@@ -494,15 +507,12 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
   const { notify } = useNotification()
   const item = data.items[index]
   const domain = item.domain
-  const isRecentlyClosedContext = data.context === Context.RecentlyClosed
   const [faviconUrl, setFaviconUrl] = useState<string | undefined>(() =>
     getFavicon(domain)
   )
   const rowDisplay = data.rowDisplay
   const isRowSelected = data.selected === index
-  const isRowClosedItem = data.closedItems.includes(
-    `${item.windowId}:${item.id}`
-  )
+  const isRowClosedItem = data.closedItems.includes(tabKey(item))
   const selectionClassName = (isRowSelected && "sliBox") || "liBox"
   const closedClassName = (isRowClosedItem && "closedItemBox") || ""
 
@@ -540,6 +550,10 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
     .map(({ domain }) => domain)
     [index].replace("www.", "")
   const url = data.items.map(({ url }) => url)[index]
+  const tabState =
+    (item.pinned ? "\u{1F4CC} " : "") +
+    (item.muted ? "\u{1F507} " : "") +
+    (item.discarded ? "\u{1F4A4} " : "")
 
   const handleOnEditButtonClick = async (e: React.MouseEvent) => {
     e.preventDefault()
@@ -553,9 +567,9 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
   }
   const handleOnCloseButtonClick = async (e: React.MouseEvent) => {
     e.preventDefault()
-    const actionId = `${item.windowId}:${item.id}`
+    const actionId = tabKey(item)
     if (data.closedItems.includes(actionId)) return
-    await closeTabAction(actionId)
+    await closeTabAction(item)
     data.setClosedItems([...data.closedItems, actionId])
     notify("Tab closed !")
     document.getElementById("searchInput")?.focus()
@@ -589,7 +603,7 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
       clearTimeout(clickTimeout.current)
       clickTimeout.current = null
     }
-    await switchTabAction(`${item.windowId}:${item.id}`, settings.web_browser)
+    await switchTabAction(item, settings.web_browser)
     data.restoreDefaults()
     await invoke("hide")
   }
@@ -613,7 +627,7 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
             <TextSelector
               faviconUrl={faviconUrl}
               className="liFirstLine"
-              content={title}
+              content={tabState + title}
               maxLength={countUpperCaseOrNumberChars(title) < 20 ? 80 : 70}
               isRowSelected={isRowSelected}
             />
@@ -635,16 +649,14 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
                 >
                   &#x2750;
                 </button>
-                {!isRecentlyClosedContext && (
-                  <button
-                    className="rowButton"
-                    id="removeItem"
-                    onKeyDown={handleOnKeyDownButton}
-                    onClick={handleOnCloseButtonClick}
-                  >
-                    &#x2718;
-                  </button>
-                )}
+                <button
+                  className="rowButton"
+                  id="removeItem"
+                  onKeyDown={handleOnKeyDownButton}
+                  onClick={handleOnCloseButtonClick}
+                >
+                  &#x2718;
+                </button>
               </div>
             )}
           </div>
@@ -671,7 +683,9 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
             <TextSelector
               faviconUrl={faviconUrl}
               className={`liFirstLineSmall ${closedClassName}`}
-              content={(shortDomain && `${shortDomain} • `) + title}
+              content={
+                tabState + (shortDomain && `${shortDomain} • `) + title
+              }
               maxLength={
                 countUpperCaseOrNumberChars(shortDomain + title) < 12 ? 90 : 80
               }
@@ -695,16 +709,14 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
                 >
                   &#x2750;
                 </button>
-                {!isRecentlyClosedContext && (
-                  <button
-                    className="rowButton"
-                    id="removeItem"
-                    onKeyDown={handleOnKeyDownButton}
-                    onClick={handleOnCloseButtonClick}
-                  >
-                    &#x2718;
-                  </button>
-                )}
+                <button
+                  className="rowButton"
+                  id="removeItem"
+                  onKeyDown={handleOnKeyDownButton}
+                  onClick={handleOnCloseButtonClick}
+                >
+                  &#x2718;
+                </button>
               </div>
             )}
           </div>
@@ -713,3 +725,190 @@ export const TabRow = ({ index, style, data }: RowProps<TabItem>) => {
     </div>
   )
 }
+
+export const RecentlyClosedRow = ({
+  index,
+  style,
+  data,
+}: RowProps<RecentlyClosedItem>) => {
+  /* Adding style attribute is very important here
+    it supplies the row height to the elements. */
+  const {
+    settings: { appSettings: settings },
+  } = useSettings()
+  const { notify } = useNotification()
+  const item = data.items[index]
+  const domain = item.domain
+  const [faviconUrl, setFaviconUrl] = useState<string | undefined>(() =>
+    getFavicon(domain)
+  )
+  const rowDisplay = data.rowDisplay
+  const isRowSelected = data.selected === index
+  const isRowClosedItem = data.closedItems.includes(closedKey(item))
+  const selectionClassName = (isRowSelected && "sliBox") || "liBox"
+  const closedClassName = (isRowClosedItem && "closedItemBox") || ""
+
+  useEffect(() => {
+    /*
+     * When unmounting the selected row,
+     * put the focus back on the searchInput.
+     * ( see TabRow )
+     */
+    return () => {
+      document.getElementById("searchInput")?.focus()
+    }
+  }, [data.selected])
+
+  useEffect(() => {
+    if (settings.show_favicons) {
+      const url = item.url.startsWith(FILE_PREFIX_URL)
+        ? FILE_PREFIX_URL
+        : domain && `https://www.google.com/s2/favicons?sz=128&domain=${domain}`
+      setFaviconUrl(url)
+      setFavicon(domain, url)
+    } else {
+      clearFaviconCache()
+    }
+  }, [domain])
+
+  const title = item.title
+  const shortDomain = domain.replace("www.", "")
+  /* the CLI does not tell when an item was closed */
+  const closedAt = item.closedAt ? new Date(item.closedAt) : undefined
+  const closedAtLocalTime = closedAt
+    ? `${closedAt.toLocaleDateString(
+        settings.date_locale || undefined
+      )}  ${closedAt.toLocaleTimeString(settings.date_locale || undefined)} • `
+    : ""
+  const closedWhat =
+    item.kind === "window"
+      ? `window of ${item.tabCount} tab${item.tabCount > 1 ? "s" : ""}`
+      : "tab"
+
+  const handleOnEditButtonClick = async (e: React.MouseEvent) => {
+    e.preventDefault()
+    data.setShowEditionTab(true)
+  }
+  const handleOnCopyUrlButtonClick = async (e: React.MouseEvent) => {
+    e.preventDefault()
+    await copyUrlToClipboard(item.url)
+    notify("Url copied !")
+    document.getElementById("searchInput")?.focus()
+  }
+  const handleOnKeyDownButton = (e: React.KeyboardEvent) => {
+    if (e.key === "Enter") e.stopPropagation()
+  }
+
+  /*
+   * Delay onClick until we know onDoubleClick is not fired ( see TabRow )
+   */
+  const clickTimeout = useRef<number | null>(null)
+
+  const handleClick = (e: React.MouseEvent) => {
+    clickTimeout.current = window.setTimeout(() => {
+      data.setClickCoordinateY(e.pageY)
+      data.setSelection(index)
+    }, 250)
+  }
+
+  const handleDoubleClick = async () => {
+    if (clickTimeout.current) {
+      clearTimeout(clickTimeout.current)
+      clickTimeout.current = null
+    }
+    if (isRowClosedItem) return
+    try {
+      await restoreClosedAction(item, settings.web_browser)
+      data.restoreDefaults()
+      await invoke("hide")
+    } catch (error) {
+      notify(describeMozeidonError(error))
+    }
+  }
+  useEffect(() => {
+    return () => {
+      if (clickTimeout.current) {
+        clearTimeout(clickTimeout.current)
+      }
+    }
+  }, [])
+
+  const buttons = isRowSelected && !isRowClosedItem && (
+    <div className="rowButtonsContainer">
+      <button
+        className="rowButton"
+        id="editBookmark"
+        onKeyDown={handleOnKeyDownButton}
+        onClick={handleOnEditButtonClick}
+      >
+        &#x2605;
+      </button>
+      <button
+        className="rowButton"
+        id="copyUrl"
+        onKeyDown={handleOnKeyDownButton}
+        onClick={handleOnCopyUrlButtonClick}
+      >
+        &#x2750;
+      </button>
+    </div>
+  )
+
+  return (
+    <div style={style} onClick={handleClick}>
+      {rowDisplay === RowDisplay.MultiLine ? (
+        <div
+          className={`${selectionClassName} ${closedClassName}`}
+          style={{ cursor: "default" }}
+          onDoubleClick={handleDoubleClick}
+        >
+          <div className="rowWithButtons">
+            <TextSelector
+              faviconUrl={faviconUrl}
+              className="liFirstLine"
+              content={title}
+              maxLength={countUpperCaseOrNumberChars(title) < 20 ? 80 : 70}
+              isRowSelected={isRowSelected}
+            />
+            {buttons}
+          </div>
+          <TextSelector
+            className="liLine"
+            content={item.url}
+            maxLength={80}
+            isRowSelected={isRowSelected}
+          />
+          <TextSelector
+            className="liLine"
+            content={`${closedAtLocalTime}closed ${closedWhat}`}
+            maxLength={80}
+            isRowSelected={isRowSelected}
+          />
+        </div>
+      ) : (
+        <div
+          className={`${selectionClassName}`}
+          style={{ cursor: "default" }}
+          onDoubleClick={handleDoubleClick}
+        >
+          <div className="rowWithButtons">
+            <TextSelector
+              faviconUrl={faviconUrl}
+              className={`liFirstLineSmall ${closedClassName}`}
+              content={
+                (item.kind === "window" ? `[${closedWhat}] ` : "") +
+                (shortDomain && `${shortDomain} • `) +
+                title
+              }
+              maxLength={
+                countUpperCaseOrNumberChars(shortDomain + title) < 12 ? 90 : 80
+              }
+              isRowSelected={isRowSelected}
+            />
+            {buttons}
+          </div>
+        </div>
+      )}
+    </div>
+  )
+}
@@ -1,19 +1,29 @@
 import { forwardRef } from "react"
+import { QueryDiagnostic } from "../utils/searchHandler"
 
 interface SearchInputProps {
   value: string
+  diagnostics?: QueryDiagnostic[]
   onChange: React.ChangeEventHandler<HTMLInputElement>
 }
 
 export const SearchInput = forwardRef<HTMLInputElement, SearchInputProps>(
-  ({ value, onChange }: SearchInputProps, ref) => {
+  ({ value, diagnostics = [], onChange }: SearchInputProps, ref) => {
+    /* Terms the search left out are listed in the tooltip */
+    const title = diagnostics
+      .map(
+        ({ message, start, end }) =>
+          `${value.trim().slice(start, end)}: ${message}`
+      )
+      .join("\n")
     return (
       <input
         ref={ref}
-        className="row"
+        className={diagnostics.length ? "row invalidQuery" : "row"}
         id="searchInput"
         autoComplete="off"
         autoCorrect="off"
+        title={title || undefined}
         value={value}
         onChange={onChange}
       />
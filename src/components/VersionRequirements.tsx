@@ -1,29 +1,64 @@
+import { useEffect, useState } from "react"
+import { invoke } from "@tauri-apps/api/core"
 import chromeLogo from "../assets/chrome.svg"
 import firefoxLogo from "../assets/firefox.svg"
+import { ComponentVersion, VersionReport } from "../domain/versions/models"
+
+const browserLogos: Record<string, string> = {
+  firefox: firefoxLogo,
+  chrome: chromeLogo,
+}
 
 export const VersionRequirements = () => {
+  const [report, setReport] = useState<VersionReport | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    invoke<VersionReport>("check_versions")
+      .then(setReport)
+      .catch((e) => setError(String(e)))
+  }, [])
+
+  if (error) return <div>Could not check versions : {error}</div>
+  if (!report) return <div>Checking versions...</div>
+
   return (
     <div>
       <ul>
-        <li style={{ lineHeight: 1.5 }}>
-          <span style={{ cursor: "pointer" }}>
-            browser-extension &nbsp;
-            <img
-              src={firefoxLogo}
-              style={{ width: "1em", height: "1em", verticalAlign: "middle" }}
-            />{" "}
-            <code>&ge;</code> 1.0.0
-          </span>
-          <span>&nbsp; or &nbsp;</span>
-          <span style={{ cursor: "pointer" }}>
-            <img
-              src={chromeLogo}
-              style={{ width: ".9em", height: ".9em", verticalAlign: "middle" }}
-            />{" "}
-            <code>&ge;</code> 1.0.0
-          </span>
-        </li>
+        {report.components.map((component) => (
+          <ComponentVersionRow
+            key={`${component.component}:${component.browser ?? ""}`}
+            component={component}
+          />
+        ))}
       </ul>
     </div>
   )
 }
+
+const ComponentVersionRow = ({
+  component,
+}: {
+  component: ComponentVersion
+}) => {
+  const logo = component.browser && browserLogos[component.browser]
+  return (
+    <li style={{ lineHeight: 1.5 }} title={component.error}>
+      <span>
+        {component.component} &nbsp;
+        {logo ? (
+          <img
+            src={logo}
+            style={{ width: "1em", height: "1em", verticalAlign: "middle" }}
+          />
+        ) : (
+          component.browser
+        )}{" "}
+        <code>{component.required}</code>
+      </span>
+      <span>
+        &nbsp; : {component.version ?? "?"} ({component.verdict})
+      </span>
+    </li>
+  )
+}
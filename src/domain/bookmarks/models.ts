@@ -1,6 +1,34 @@
-export type BookmarkItem = {
-  id: string
-  parent: string
+export type { BookmarkItem } from "../generated/BookmarkItem"
+export type { BookmarkFolder } from "../generated/BookmarkFolder"
+export type { BookmarkTree } from "../generated/BookmarkTree"
+
+import type { BookmarkFolder } from "../generated/BookmarkFolder"
+
+/*
+ * Every folder of the tree, the root included, parents first.
+ */
+export function flattenFolders(folder: BookmarkFolder): BookmarkFolder[] {
+  return [folder, ...folder.children.flatMap(flattenFolders)]
+}
+
+/*
+ * A bookmark read from a Netscape bookmark file.
+ */
+export type ImportedBookmark = {
   title: string
   url: string
+  folder: string
+}
+
+export type ImportPreview = {
+  newFolders: string[]
+  newBookmarks: ImportedBookmark[]
+  duplicates: ImportedBookmark[]
+}
+
+export type ImportSummary = {
+  createdFolders: number
+  createdBookmarks: number
+  skippedDuplicates: number
+  errors: string[]
 }
@@ -0,0 +1,15 @@
+export type { ItemKind } from "../generated/ItemKind"
+
+import type { BookmarkItem } from "../generated/BookmarkItem"
+import type { HistoryItem } from "../generated/HistoryItem"
+import type { TabItem } from "../generated/TabItem"
+
+/*
+ * One item per url, the open tab winning over the bookmark,
+ * and the bookmark over the history row.
+ * `score` is the frecency of the url.
+ */
+export type EverythingItem =
+  | (TabItem & { kind: "tab"; score: number })
+  | (BookmarkItem & { kind: "bookmark"; score: number })
+  | (HistoryItem & { kind: "history"; score: number })
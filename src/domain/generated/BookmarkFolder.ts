@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A bookmark folder, and the folders it contains.
+ */
+export type BookmarkFolder = { name: string, 
+/**
+ * Folder path, e.g. `/Bookmarks Toolbar/dev/`, as in `BookmarkItem::parent`.
+ */
+path: string, 
+/**
+ * Bookmarks directly in this folder.
+ */
+itemCount: number, 
+/**
+ * Bookmarks in this folder and its subfolders.
+ */
+totalCount: number, children: Array<BookmarkFolder>, };
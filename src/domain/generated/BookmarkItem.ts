@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type BookmarkItem = { url: string, title: string, id: string, 
+/**
+ * Folder path, e.g. `/Bookmarks Toolbar/dev/`.
+ */
+parent: string, browser?: string, };
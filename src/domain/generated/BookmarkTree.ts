@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BookmarkFolder } from "./BookmarkFolder";
+
+/**
+ * The folders of one browser, under a root folder of path `/`.
+ */
+export type BookmarkTree = { browser?: string, root: BookmarkFolder, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Whether a recently closed item is a single tab or a whole window.
+ */
+export type ClosedKind = "tab" | "window";
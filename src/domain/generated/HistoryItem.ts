@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The CLI prints the counters and the visit time under one-letter keys,
+ * which are still accepted as aliases.
+ */
+export type HistoryItem = { url: string, title: string, id: string, 
+/**
+ * How many times the url was typed in the address bar.
+ */
+typedCount: number, visitCount: number, 
+/**
+ * Milliseconds since the epoch.
+ */
+lastVisitTime: number, browser?: string, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where an item of `get_everything` comes from.
+ * Declared in order of precedence: an open tab hides the bookmark of the same url,
+ * which hides its history row.
+ */
+export type ItemKind = "tab" | "bookmark" | "history";
@@ -0,0 +1,34 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ClosedKind } from "./ClosedKind";
+
+/**
+ * A tab or a window closed lately, as listed by `tabs get -c`.
+ * The CLI prints them as tabs, the other fields are only filled in
+ * by backends that know them, e.g. the simulated browser.
+ */
+export type RecentlyClosedItem = { 
+/**
+ * The id the tab had before it was closed.
+ */
+id: number, 
+/**
+ * Handed back to the browser to restore the tab or the window.
+ * The item cannot be restored without it.
+ */
+sessionId?: string, kind: ClosedKind, domain: string, title: string, 
+/**
+ * For a window, the url of its active tab.
+ */
+url: string, 
+/**
+ * The window the tab belonged to, or the closed window itself.
+ */
+windowId: number, 
+/**
+ * How many tabs a closed window had.
+ */
+tabCount: number, 
+/**
+ * Milliseconds since the epoch, 0 when unknown.
+ */
+closedAt: number, browser?: string, };
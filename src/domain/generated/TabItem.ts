@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An open tab, as listed by `tabs get`.
+ * The CLI does not print the tab states, they are `None` unless
+ * a backend knows them, e.g. the simulated browser.
+ */
+export type TabItem = { id: number, domain: string, title: string, url: string, windowId: number, 
+/**
+ * The selected tab of its window.
+ */
+active?: boolean, 
+/**
+ * Milliseconds since the epoch.
+ */
+lastAccessed?: number, pinned?: boolean, muted?: boolean, 
+/**
+ * Unloaded from memory, until switched to again.
+ */
+discarded?: boolean, browser?: string, };
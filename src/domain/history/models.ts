@@ -1,8 +1,24 @@
-export type HistoryItem = {
-  id: string
-  title: string
-  url: string
-  t: number
-  vt: number
-  vc: number
+export type { HistoryItem } from "../generated/HistoryItem"
+
+import type { HistoryItem } from "../generated/HistoryItem"
+
+export type HistoryStatsOptions = {
+  days?: number
+  top?: number
+  minVisits?: number
+  utcOffsetMinutes?: number
+}
+
+/*
+ * The history only tells when each page was last visited,
+ * so the visits per day and per hour count pages by their last visit.
+ */
+export type HistoryStats = {
+  pages: number
+  visits: number
+  topDomains: { domain: string; visits: number; typed: number; pages: number }[]
+  lastVisitsPerDay: { day: string; pages: number }[]
+  lastVisitsPerHour: number[]
+  newDomains: { domain: string; oldestLastVisitTime: number; pages: number }[]
+  unbookmarked: HistoryItem[]
 }
@@ -0,0 +1,60 @@
+/*
+ * Mirrors the ClientError kinds serialized by the Rust side.
+ */
+export type MozeidonErrorKind =
+  | "cliNotFound"
+  | "spawn"
+  | "nativeAppUnreachable"
+  | "extensionNotResponding"
+  | "malformedOutput"
+  | "unexpectedOutput"
+  | "nonZeroExit"
+  | "process"
+  | "cancelled"
+  | "timedOut"
+  | "unsupported"
+  | "nothingToUndo"
+  | "invalidBookmarkChange"
+  | "bookmarkFileAccess"
+  | "invalidBookmarkFile"
+
+export type MozeidonError = {
+  kind: MozeidonErrorKind
+  message: string
+}
+
+export function isMozeidonError(error: unknown): error is MozeidonError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    "kind" in error &&
+    "message" in error
+  )
+}
+
+/*
+ * A cancelled query was superseded by a newer one, or the panel was hidden :
+ * there is nothing to tell the user about.
+ */
+export function isCancelledError(error: unknown): boolean {
+  return isMozeidonError(error) && error.kind === "cancelled"
+}
+
+export function describeMozeidonError(error: unknown): string {
+  if (!isMozeidonError(error)) return `${error}`
+  switch (error.kind) {
+    case "cliNotFound":
+      return "mozeidon-cli is missing, please reinstall the app"
+    case "nativeAppUnreachable":
+      return "mozeidon native app is unreachable, is your browser running ?"
+    case "extensionNotResponding":
+      return "mozeidon browser extension is not responding"
+    case "malformedOutput":
+    case "unexpectedOutput":
+      return "unexpected output from mozeidon-cli"
+    case "timedOut":
+      return "mozeidon took too long to answer"
+    default:
+      return error.message
+  }
+}
@@ -0,0 +1,38 @@
+/*
+ * Mirrors the MozeidonRequest enum on the Rust side,
+ * which owns how each request turns into mozeidon-cli arguments.
+ */
+export type MozeidonRequest =
+  | { type: "getTabs" }
+  | { type: "getRecentlyClosed" }
+  | { type: "switchTab"; id: number; windowId: number }
+  | { type: "closeTab"; id: number; windowId: number }
+  | { type: "closeTabs"; tabs: { id: number; windowId: number }[] }
+  | { type: "openTab"; url: string }
+  | { type: "pinTab"; id: number; windowId: number; pinned: boolean }
+  | { type: "muteTab"; id: number; windowId: number; muted: boolean }
+  | { type: "reloadTab"; id: number; windowId: number }
+  | { type: "duplicateTab"; id: number; windowId: number }
+  | { type: "discardTab"; id: number; windowId: number }
+  | {
+      type: "moveTab"
+      id: number
+      windowId: number
+      toWindowId?: number
+      index?: number
+    }
+  | { type: "restoreClosed"; sessionId: string }
+  | { type: "getBookmarks"; max?: number; chunk?: number }
+  | { type: "getHistory"; max?: number; chunk?: number }
+  | { type: "createBookmark"; title: string; url: string; folder: string }
+  | {
+      type: "updateBookmark"
+      id: string
+      title: string
+      url: string
+      folder: string
+    }
+  | { type: "deleteBookmark"; id: string }
+  | { type: "getFolders" }
+  | { type: "createFolder"; path: string }
+  | { type: "getExtensionVersion" }
@@ -0,0 +1,11 @@
+import type { RecentlyClosedItem } from "../generated/RecentlyClosedItem"
+
+export type { RecentlyClosedItem }
+
+/*
+ * Session ids are only unique within a browser.
+ * The CLI does not print them, its items are told apart by tab id.
+ */
+export function closedKey(item: RecentlyClosedItem): string {
+  return `${item.browser ?? ""}:${item.sessionId ?? item.id}`
+}
@@ -0,0 +1,21 @@
+import { SearchType } from "../../utils/searchHandler"
+
+export type SavedSearchContext =
+  | "tabs"
+  | "recentlyClosed"
+  | "bookmarks"
+  | "history"
+
+/*
+ * A query on the items of a context, kept to be run again like a smart folder.
+ * `id` is given by the backend when saving a new search.
+ * `globalShortcut` opens the panel on the search, it is empty when there is none.
+ */
+export type SavedSearch = {
+  id: string
+  name: string
+  context: SavedSearchContext
+  query: string
+  searchType: SearchType
+  globalShortcut: string
+}
@@ -15,10 +15,25 @@ type Shortcuts = {
   shortcut_list_down: string
   shortcut_list_up: string
   shortcut_edit_bookmark: string
+  shortcut_pin_tab: string
+  shortcut_mute_tab: string
+  shortcut_reload_tab: string
+  shortcut_duplicate_tab: string
+  shortcut_discard_tab: string
+  shortcut_move_tab_to_new_window: string
+  shortcut_close_matching_tabs: string
+  shortcut_close_other_tabs_in_window: string
+  shortcut_close_tabs_of_domain: string
+  shortcut_undo_close_tabs: string
 }
 
+export type Backend = "sidecar" | "simulated"
+
 export type AppSettings = {
   theme: string
+  backend: Backend
+  browsers: string[]
+  health_monitor: boolean
   web_browser: string
   web_search_engine_urls: string[]
   date_locale: string
@@ -44,6 +59,9 @@ export type Settings = {
 
 export const defaultSettings: AppSettings = {
   theme: "system",
+  backend: "sidecar",
+  browsers: [],
+  health_monitor: false,
   web_browser: "firefox",
   web_search_engine_urls: [
     "https://www.google.com/search?q=",
@@ -62,6 +80,16 @@ export const defaultSettings: AppSettings = {
   shortcut_list_down: "ArrowDown",
   shortcut_list_up: "ArrowUp",
   shortcut_edit_bookmark: "Control+m",
+  shortcut_pin_tab: "",
+  shortcut_mute_tab: "",
+  shortcut_reload_tab: "",
+  shortcut_duplicate_tab: "",
+  shortcut_discard_tab: "",
+  shortcut_move_tab_to_new_window: "",
+  shortcut_close_matching_tabs: "",
+  shortcut_close_other_tabs_in_window: "",
+  shortcut_close_tabs_of_domain: "",
+  shortcut_undo_close_tabs: "",
 }
 
 export function getGlobalShortcuts(settings: AppSettings): GlobalShortcuts {
@@ -84,5 +112,16 @@ export function getShortcuts(settings: AppSettings): Shortcuts {
     shortcut_list_up: settings.shortcut_list_up,
     shortcut_list_down: settings.shortcut_list_down,
     shortcut_edit_bookmark: settings.shortcut_edit_bookmark,
+    shortcut_pin_tab: settings.shortcut_pin_tab,
+    shortcut_mute_tab: settings.shortcut_mute_tab,
+    shortcut_reload_tab: settings.shortcut_reload_tab,
+    shortcut_duplicate_tab: settings.shortcut_duplicate_tab,
+    shortcut_discard_tab: settings.shortcut_discard_tab,
+    shortcut_move_tab_to_new_window: settings.shortcut_move_tab_to_new_window,
+    shortcut_close_matching_tabs: settings.shortcut_close_matching_tabs,
+    shortcut_close_other_tabs_in_window:
+      settings.shortcut_close_other_tabs_in_window,
+    shortcut_close_tabs_of_domain: settings.shortcut_close_tabs_of_domain,
+    shortcut_undo_close_tabs: settings.shortcut_undo_close_tabs,
   }
 }
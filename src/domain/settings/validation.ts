@@ -47,6 +47,9 @@ const WebSearchUrl = v.pipe(
 
 export const SettingsSchema = v.object({
   theme: v.union([v.literal("system"), v.literal("dark"), v.literal("light")]),
+  backend: v.union([v.literal("sidecar"), v.literal("simulated")]),
+  browsers: v.array(v.string()),
+  health_monitor: v.boolean(),
   web_browser: v.string(),
   web_search_engine_urls: v.array(WebSearchUrl),
   date_locale: OptionalLocale,
@@ -61,6 +64,16 @@ export const SettingsSchema = v.object({
   shortcut_list_down: Shortcut,
   shortcut_list_up: Shortcut,
   shortcut_edit_bookmark: Shortcut,
+  shortcut_pin_tab: Shortcut,
+  shortcut_mute_tab: Shortcut,
+  shortcut_reload_tab: Shortcut,
+  shortcut_duplicate_tab: Shortcut,
+  shortcut_discard_tab: Shortcut,
+  shortcut_move_tab_to_new_window: Shortcut,
+  shortcut_close_matching_tabs: Shortcut,
+  shortcut_close_other_tabs_in_window: Shortcut,
+  shortcut_close_tabs_of_domain: Shortcut,
+  shortcut_undo_close_tabs: Shortcut,
 })
 
 export function validateSettingsForm(
@@ -1,7 +1,28 @@
-export type TabItem = {
-  id: number
-  domain: string
-  title: string
-  url: string
-  windowId: number
+import type { TabItem } from "../generated/TabItem"
+
+export type { TabItem }
+
+/*
+ * Mirrors the TabFilter enum of the bulk tab commands.
+ */
+export type TabFilter =
+  | { type: "domain"; domain: string }
+  | { type: "window"; windowId: number; exceptActive: boolean }
+  | { type: "olderThan"; ageMs: number }
+  | { type: "shown"; tabs: ShownTab[] }
+
+export type ShownTab = Pick<TabItem, "id" | "windowId" | "browser">
+
+export type BulkSummary = {
+  closed: TabItem[]
+  errors: string[]
+  undoToken?: string
+}
+
+/*
+ * Tab ids are only unique within a browser,
+ * and several browsers can be queried at once.
+ */
+export function tabKey(tab: TabItem): string {
+  return `${tab.browser ?? ""}:${tab.windowId}:${tab.id}`
 }
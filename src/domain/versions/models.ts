@@ -0,0 +1,24 @@
+/*
+ * Mirrors the VersionReport returned by the check_versions command.
+ */
+export type Component =
+  | "mozeidon-cli"
+  | "mozeidon-native-app"
+  | "browser-extension"
+
+export type Verdict = "ok" | "outdated" | "unknown"
+
+export type ComponentVersion = {
+  component: Component
+  browser?: string
+  version: string | null
+  required: string
+  verdict: Verdict
+  error?: string
+}
+
+export type VersionReport = {
+  components: ComponentVersion[]
+  // False only when a component is outdated, unknown versions do not count.
+  compatible: boolean
+}
@@ -21,13 +21,18 @@ import {
   defaultSettings,
   getGlobalShortcuts,
 } from "../domain/settings/models"
-import { emit } from "@tauri-apps/api/event"
+import { emit, listen } from "@tauri-apps/api/event"
 import {
   AUTO_CONFIGURED_BROWSERS,
   INACTIVE_SHORTCUT_VALUE,
 } from "../utils/constants"
 import { applyTheme } from "../utils/applyTheme"
-import { getBrowserManifests, getUserHomeDir } from "../actions/actions"
+import {
+  getBrowserManifests,
+  getUserHomeDir,
+  listSavedSearchesAction,
+} from "../actions/actions"
+import { SavedSearch } from "../domain/savedSearches/models"
 
 type SettingsContextType = {
   settings: Settings
@@ -43,17 +48,20 @@ type SettingsProviderProps = {
   shortcutsHandlers: {
     [K in GlobalShortcutsKey]: () => Promise<void>
   }
+  savedSearchHandler: (search: SavedSearch) => Promise<void>
 }
 
 export const SettingsProvider: React.FC<SettingsProviderProps> = ({
   children,
   shortcutsHandlers,
+  savedSearchHandler,
 }) => {
   /* initialize settings to undefined */
   const [settings, setSettingsState] = useState<Settings | undefined>(undefined)
   const [isDirty, setIsDirty] = useState(false)
   const [shouldRegister, setShouldRegister] = useState(false)
   const previousSettingsRef = useRef<Settings | undefined>(undefined)
+  const savedSearchShortcutsRef = useRef<string[]>([])
 
   // At start, load settings from server
   useEffect(() => {
@@ -160,6 +168,41 @@ export const SettingsProvider: React.FC<SettingsProviderProps> = ({
     }
   }, [shouldRegister])
 
+  // Register the global-shortcuts of saved searches, again whenever they change
+  useEffect(() => {
+    const registerSavedSearches = async () => {
+      const searches = await listSavedSearchesAction()
+      for (const shortcut of savedSearchShortcutsRef.current) {
+        if (await isRegistered(shortcut)) {
+          await unregister(shortcut)
+        }
+      }
+      savedSearchShortcutsRef.current = []
+      for (const search of searches) {
+        if (search.globalShortcut === INACTIVE_SHORTCUT_VALUE) {
+          continue
+        }
+        try {
+          await register(search.globalShortcut, (event) => {
+            if (event.state === "Pressed") {
+              savedSearchHandler(search)
+            }
+          })
+          savedSearchShortcutsRef.current.push(search.globalShortcut)
+        } catch (e) {
+          await emit("js-message", { message: `${JSON.stringify(e)}` })
+        }
+      }
+    }
+    registerSavedSearches().catch(() => {})
+    const unlisten = listen("saved-searches-changed", () => {
+      registerSavedSearches().catch(() => {})
+    })
+    return () => {
+      unlisten.then((f) => f())
+    }
+  }, [])
+
   // Wrap setSettings to track user changes
   const setSettings = (newSettings: Settings) => {
     previousSettingsRef.current = settings
@@ -1,13 +1,3 @@
-export const GET_BOOKMARKS_COMMAND = "bookmarks -m 100000 -c 1000"
-export const GET_HISTORY_COMMAND = "history -m 100000 -c 1000"
-export const CREATE_BOOKMARK_COMMAND = "bookmark new"
-export const UPDATE_BOOKMARK_COMMAND = "bookmark update"
-export const GET_TABS_COMMAND = "tabs get"
-export const GET_RECENTLY_CLOSED_COMMAND = "tabs get -c"
-export const SWITCH_TAB_COMMAND = "tabs switch"
-export const CLOSE_TAB_COMMAND = "tabs close"
-export const DELETE_BOOKMARK_COMMAND = "bookmark delete"
-
 export const AUTO_CONFIGURED_BROWSERS = ["firefox", "chrome", "edge"]
 export const BROWSER_NATIVE_MESSAGING_DIR = "NativeMessagingHosts"
 
@@ -1,24 +1,38 @@
 import { Dispatch, KeyboardEvent, SetStateAction } from "react"
 import { AppSettings, Settings } from "../domain/settings/models"
 import { Context } from "../utils/constants"
-import { TabItem } from "../domain/tabs/models"
+import { TabFilter, TabItem, tabKey } from "../domain/tabs/models"
 import { BookmarkItem } from "../domain/bookmarks/models"
 import {
   openURLAction,
   switchTabAction,
   closeTabAction,
+  closeTabsAction,
   copyUrlToClipboard,
   deleteBookmarkAction,
+  discardTabAction,
+  duplicateTabAction,
+  moveTabToNewWindowAction,
+  muteTabAction,
+  pinTabAction,
+  reloadTabAction,
+  restoreClosedAction,
+  undoCloseTabsAction,
 } from "../actions/actions"
+import { describeMozeidonError } from "../domain/mozeidon/errors"
 import { getKeyCombination } from "./getKeyCombination"
 import { HistoryItem } from "../domain/history/models"
+import {
+  RecentlyClosedItem,
+  closedKey,
+} from "../domain/recentlyClosed/models"
 import { invoke } from "@tauri-apps/api/core"
 
 type KeyDownHandlerParams = {
   event: KeyboardEvent
   settings: AppSettings
   context: Context
-  fuzzyItems: (TabItem | BookmarkItem | HistoryItem)[]
+  fuzzyItems: (TabItem | BookmarkItem | HistoryItem | RecentlyClosedItem)[]
   showEditionTab: boolean
   isWebSearch: boolean
   selectedListIndex: number
@@ -30,9 +44,62 @@ type KeyDownHandlerParams = {
   setSelectedListIndex: Dispatch<SetStateAction<number>>
   setSelectedWebSearchListIndex: Dispatch<SetStateAction<number>>
   setClosedItems: Dispatch<SetStateAction<string[]>>
+  replaceTab: (tab: TabItem, tabs: TabItem[]) => void
+  undoToken?: string
+  setUndoToken: Dispatch<SetStateAction<string | undefined>>
+  reloadTabs: () => Promise<void>
   restoreDefaults: () => void
 }
 
+type TabAction = {
+  shortcut: string
+  action: (tab: TabItem) => Promise<TabItem>
+  message: (updated: TabItem) => string
+  /* the action opens a new tab, next to the selected one */
+  keepOriginal?: boolean
+}
+
+/*
+ * mozeidon-cli has no subcommand for the tab actions,
+ * only the simulated backend answers them.
+ */
+function getTabActions(settings: AppSettings): TabAction[] {
+  if (settings.backend !== "simulated") return []
+  return [
+    {
+      shortcut: settings.shortcut_pin_tab,
+      action: pinTabAction,
+      message: (tab) => (tab.pinned ? "Tab pinned !" : "Tab unpinned !"),
+    },
+    {
+      shortcut: settings.shortcut_mute_tab,
+      action: muteTabAction,
+      message: (tab) => (tab.muted ? "Tab muted !" : "Tab unmuted !"),
+    },
+    {
+      shortcut: settings.shortcut_reload_tab,
+      action: reloadTabAction,
+      message: () => "Tab reloaded !",
+    },
+    {
+      shortcut: settings.shortcut_duplicate_tab,
+      action: duplicateTabAction,
+      message: () => "Tab duplicated !",
+      keepOriginal: true,
+    },
+    {
+      shortcut: settings.shortcut_discard_tab,
+      action: discardTabAction,
+      message: () => "Tab unloaded !",
+    },
+    {
+      shortcut: settings.shortcut_move_tab_to_new_window,
+      action: moveTabToNewWindowAction,
+      message: () => "Tab moved to a new window !",
+    },
+  ]
+}
+
 export const keyDownHandler = async ({
   event,
   settings,
@@ -49,6 +116,10 @@ export const keyDownHandler = async ({
   setSelectedListIndex,
   setSelectedWebSearchListIndex,
   setClosedItems,
+  replaceTab,
+  undoToken,
+  setUndoToken,
+  reloadTabs,
   restoreDefaults,
 }: KeyDownHandlerParams) => {
   const keyCombo = getKeyCombination(event)
@@ -91,14 +162,14 @@ export const keyDownHandler = async ({
   if (context === Context.Tabs) {
     const tab = fuzzyItems[selectedListIndex] as TabItem
     if (!tab) return
-    const actionId = `${tab.windowId}:${tab.id}`
+    const actionId = tabKey(tab)
 
     if (
       event.key === "Enter" &&
       !closedItems.includes(actionId) &&
       !showEditionTab
     ) {
-      await switchTabAction(actionId, settings.web_browser)
+      await switchTabAction(tab, settings.web_browser)
       restoreDefaults()
       await invoke("hide")
     }
@@ -108,10 +179,60 @@ export const keyDownHandler = async ({
       !closedItems.includes(actionId) &&
       !showEditionTab
     ) {
-      await closeTabAction(actionId)
+      await closeTabAction(tab)
       notify(`Tab closed !`)
       setClosedItems([...closedItems, actionId])
     }
+
+    const filter = getBulkFilter(
+      settings,
+      keyCombo,
+      tab,
+      searchTerms,
+      fuzzyItems as TabItem[]
+    )
+    if (filter && !showEditionTab) {
+      try {
+        const summary = await closeTabsAction(filter)
+        setClosedItems([...closedItems, ...summary.closed.map(tabKey)])
+        setUndoToken(summary.undoToken)
+        notify(
+          `Closed ${summary.closed.length} tabs` +
+            (summary.errors.length ? `, ${summary.errors.join(", ")}` : "")
+        )
+      } catch (error) {
+        notify(describeMozeidonError(error))
+      }
+    }
+
+    if (
+      undoToken &&
+      settings.shortcut_undo_close_tabs &&
+      keyCombo.toLowerCase() === settings.shortcut_undo_close_tabs.toLowerCase()
+    ) {
+      try {
+        const reopened = await undoCloseTabsAction(undoToken)
+        setUndoToken(undefined)
+        await reloadTabs()
+        notify(`Reopened ${reopened} tabs`)
+      } catch (error) {
+        notify(describeMozeidonError(error))
+      }
+    }
+
+    const tabAction = getTabActions(settings).find(
+      ({ shortcut }) =>
+        shortcut && keyCombo.toLowerCase() === shortcut.toLowerCase()
+    )
+    if (tabAction && !closedItems.includes(actionId) && !showEditionTab) {
+      try {
+        const updated = await tabAction.action(tab)
+        replaceTab(tab, tabAction.keepOriginal ? [tab, updated] : [updated])
+        notify(tabAction.message(updated))
+      } catch (error) {
+        notify(describeMozeidonError(error))
+      }
+    }
   }
 
   if (context === Context.Bookmarks) {
@@ -130,14 +251,32 @@ export const keyDownHandler = async ({
       !closedItems.includes(actionId) &&
       !showEditionTab
     ) {
-      await deleteBookmarkAction(actionId)
+      await deleteBookmarkAction(actionId, bookmark.browser)
       notify(`Bookmark deleted`)
       setClosedItems([...closedItems, actionId])
     }
   }
 
-  if ([Context.RecentlyClosed, Context.History].includes(context)) {
-    const item = fuzzyItems[selectedListIndex] as TabItem
+  if (context === Context.RecentlyClosed) {
+    const item = fuzzyItems[selectedListIndex] as RecentlyClosedItem
+    if (!item) return
+    if (
+      event.key === "Enter" &&
+      !closedItems.includes(closedKey(item)) &&
+      !showEditionTab
+    ) {
+      try {
+        await restoreClosedAction(item, settings.web_browser)
+        restoreDefaults()
+        await invoke("hide")
+      } catch (error) {
+        notify(describeMozeidonError(error))
+      }
+    }
+  }
+
+  if (context === Context.History) {
+    const item = fuzzyItems[selectedListIndex] as HistoryItem
     if (event.key === "Enter" && !showEditionTab) {
       await openURLAction(item.url, settings.web_browser)
       restoreDefaults()
@@ -146,6 +285,35 @@ export const keyDownHandler = async ({
   }
 }
 
+/*
+ * The filter of the bulk close bound to keyCombo, if any.
+ */
+function getBulkFilter(
+  settings: AppSettings,
+  keyCombo: string,
+  tab: TabItem,
+  searchTerms: string,
+  shownTabs: TabItem[]
+): TabFilter | undefined {
+  const isShortcut = (shortcut: string) =>
+    shortcut && keyCombo.toLowerCase() === shortcut.toLowerCase()
+  if (isShortcut(settings.shortcut_close_matching_tabs) && searchTerms.trim()) {
+    /* the tabs listed for the search, as the search box matched them */
+    const tabs = shownTabs.map(({ id, windowId, browser }) => ({
+      id,
+      windowId,
+      browser,
+    }))
+    return { type: "shown", tabs }
+  }
+  if (isShortcut(settings.shortcut_close_other_tabs_in_window)) {
+    return { type: "window", windowId: tab.windowId, exceptActive: true }
+  }
+  if (isShortcut(settings.shortcut_close_tabs_of_domain) && tab.domain) {
+    return { type: "domain", domain: tab.domain }
+  }
+}
+
 const navigationShortcutHandler =
   (
     settings: AppSettings,
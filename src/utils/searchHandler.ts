@@ -1,11 +1,17 @@
 import fuzzysort from "fuzzysort"
+import { invoke } from "@tauri-apps/api/core"
 import { HistoryItem } from "../domain/history/models"
 import { BookmarkItem } from "../domain/bookmarks/models"
 import { TabItem } from "../domain/tabs/models"
+import { RecentlyClosedItem } from "../domain/recentlyClosed/models"
 import { Context } from "./constants"
 
 /* TODO Try to improve typing */
-type Items = Array<TabItem> | Array<BookmarkItem> | Array<HistoryItem>
+type Items =
+  | Array<TabItem>
+  | Array<BookmarkItem>
+  | Array<HistoryItem>
+  | Array<RecentlyClosedItem>
 type Item = Items[keyof Items]
 
 export enum SearchType {
@@ -16,14 +22,85 @@ export enum SearchType {
 const TAB_KEYS: Array<keyof TabItem> = ["url", "title", "domain"]
 const BOOKMARK_KEYS: Array<keyof BookmarkItem> = ["url", "title", "parent"]
 const HISTORY_KEYS: Array<keyof HistoryItem> = ["url", "title"]
+const RECENTLY_CLOSED_KEYS: Array<keyof RecentlyClosedItem> = [
+  "url",
+  "title",
+  "domain",
+]
 
-export function handleSearch(
+/*
+ * The matched [start, end) ranges of each field, in string indexes.
+ */
+export type Highlights = Partial<Record<string, [number, number][]>>
+
+/*
+ * How search results are ranked, see `RankingWeights` in ranking.rs.
+ * A result's score is matchWeight * match + frecencyWeight * frecency.
+ */
+export type RankingWeights = {
+  matchWeight?: number
+  frecencyWeight?: number
+  typedVisits?: number
+  halfLifeDays?: number
+}
+
+export type SearchHit<T> = {
+  item: T
+  /* Higher is better, hits come sorted by it */
+  score: number
+  highlights: Highlights
+}
+
+/*
+ * A term of the query left out of the search,
+ * at [start, end) in the query, in string indexes.
+ */
+export type QueryDiagnostic = {
+  message: string
+  start: number
+  end: number
+}
+
+export type SearchResults<T> = {
+  hits: SearchHit<T>[]
+  total: number
+  diagnostics: QueryDiagnostic[]
+}
+
+const SEARCH_LIMIT = 500
+
+/*
+ * Searches the items indexed on the Rust side when the panel read them,
+ * or falls back to searching `items` here when there is no index yet.
+ * The Rust side understands filters like `domain:github.com`, `-negated` terms
+ * and "quoted phrases", and reports the terms it could not understand.
+ */
+export async function handleSearch(
   setFuzzyItems: React.Dispatch<React.SetStateAction<Items>>,
   searchType: SearchType,
   searchTerms: string,
   context: Context,
-  items: Items
+  items: Items,
+  setDiagnostics: (diagnostics: QueryDiagnostic[]) => void = () => {}
 ) {
+  const queryContext = getQueryContext(context)
+  if (queryContext) {
+    try {
+      const results: SearchResults<Item> = await invoke("search", {
+        context: queryContext,
+        query: searchTerms.trim(),
+        searchType,
+        limit: SEARCH_LIMIT,
+        utcOffsetMinutes: -new Date().getTimezoneOffset(),
+      })
+      setFuzzyItems(results.hits.map((hit) => hit.item) as Items)
+      setDiagnostics(results.diagnostics)
+      return
+    } catch (e) {
+      console.warn("Native search failed, searching in the webview", e)
+    }
+  }
+  setDiagnostics([])
   const res = getSearchResults<Item>(
     searchType,
     searchTerms.trim(),
@@ -41,7 +118,11 @@ function getSearchResults<T extends Item>(
   searchType: SearchType,
   searchTerms: string,
   items: Array<T>,
-  itemKeys: typeof TAB_KEYS | typeof BOOKMARK_KEYS | typeof HISTORY_KEYS
+  itemKeys:
+    | typeof TAB_KEYS
+    | typeof BOOKMARK_KEYS
+    | typeof HISTORY_KEYS
+    | typeof RECENTLY_CLOSED_KEYS
 ) {
   switch (searchType) {
     case SearchType.Fuzzy:
@@ -67,13 +148,32 @@ function getSearchResults<T extends Item>(
   }
 }
 
+/*
+ * The QueryContext the Rust side indexes the items of `context` under.
+ */
+function getQueryContext(context: Context) {
+  switch (context) {
+    case Context.Tabs:
+      return "tabs"
+    case Context.RecentlyClosed:
+      return "recentlyClosed"
+    case Context.Bookmarks:
+      return "bookmarks"
+    case Context.History:
+      return "history"
+    default:
+      return undefined
+  }
+}
+
 function getKeys(context: Context) {
   switch (context) {
     case Context.None:
     case Context.Settings:
     case Context.Tabs:
-    case Context.RecentlyClosed:
       return TAB_KEYS
+    case Context.RecentlyClosed:
+      return RECENTLY_CLOSED_KEYS
     case Context.History:
       return HISTORY_KEYS
     case Context.Bookmarks:
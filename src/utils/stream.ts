@@ -0,0 +1,49 @@
+import { Channel, invoke } from "@tauri-apps/api/core"
+import { MozeidonRequest } from "../domain/mozeidon/requests"
+import { MozeidonError } from "../domain/mozeidon/errors"
+
+/*
+ * A line of mozeidon-cli output that could not be decoded, and was skipped.
+ */
+type MalformedLine = {
+  browser?: string
+  line: number
+  error: string
+  excerpt: string
+}
+
+type StreamEvent<T> =
+  | { event: "chunk"; data: { items: T[] } }
+  | { event: "end"; data: { count: number; malformed: MalformedLine[] } }
+  | { event: "error"; data: MozeidonError }
+
+/*
+ * Streams the items of a mozeidon query :
+ * onItems is called for every chunk decoded on the Rust side,
+ * and the returned promise resolves with the total count once the stream ends.
+ */
+export function streamMozeidon<T>(
+  request: MozeidonRequest,
+  onItems: (items: T[]) => void
+): Promise<number> {
+  return new Promise((resolve, reject) => {
+    const onEvent = new Channel<StreamEvent<T>>()
+    onEvent.onmessage = (message) => {
+      switch (message.event) {
+        case "chunk":
+          onItems(message.data.items)
+          break
+        case "end":
+          for (const malformed of message.data.malformed) {
+            console.warn("skipped mozeidon output", malformed)
+          }
+          resolve(message.data.count)
+          break
+        case "error":
+          reject(message.data)
+          break
+      }
+    }
+    invoke("mozeidon_stream", { request, onEvent }).catch(reject)
+  })
+}